@@ -0,0 +1,98 @@
+use super::Float;
+use super::vec3::Vec3;
+
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: Vec3::new(Float::MAX, Float::MAX, Float::MAX),
+            max: Vec3::new(Float::MIN, Float::MIN, Float::MIN),
+        }
+    }
+
+    pub fn from_points(points: &[&Vec3]) -> Self {
+        let mut aabb = Self::empty();
+        for point in points {
+            aabb.grow(point);
+        }
+
+        aabb
+    }
+
+    pub fn grow(&mut self, point: &Vec3) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self::new(self.min.min(&other.min), self.max.max(&other.max))
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // Slab test, returns the parametric interval the ray spends inside the box.
+    pub fn ray_interval(
+        &self,
+        origin: &Vec3,
+        direction: &Vec3,
+        t_max: Float,
+    ) -> Option<(Float, Float)> {
+        let mut t_enter: Float = 0.;
+        let mut t_exit = t_max;
+
+        for axis in 0..3 {
+            let inv = 1. / direction[axis];
+            let mut t0 = (self.min[axis] - origin[axis]) * inv;
+            let mut t1 = (self.max[axis] - origin[axis]) * inv;
+            if inv < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // NaN appears when the ray is parallel to a slab and starts on its plane.
+            if !t0.is_nan() {
+                t_enter = t_enter.max(t0);
+            }
+            if !t1.is_nan() {
+                t_exit = t_exit.min(t1);
+            }
+
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+
+        Some((t_enter, t_exit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::aabb::Aabb;
+    use crate::geometry::vec3::Vec3;
+
+    #[test]
+    fn ray_interval() {
+        let aabb = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let orig = Vec3::new(0., 0., 5.);
+        let dir = Vec3::new(0., 0., -1.);
+
+        let (t0, t1) = aabb.ray_interval(&orig, &dir, Float::MAX).unwrap();
+
+        assert_eq!(t0, 4.);
+        assert_eq!(t1, 6.);
+        assert!(aabb
+            .ray_interval(&orig, &Vec3::new(0., 1., 0.), Float::MAX)
+            .is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use super::Float;
+use super::aabb::Aabb;
+use super::vec3::Vec3;
+
+const MAX_LEAF_SIZE: usize = 4;
+
+enum Node {
+    Leaf { bounds: Aabb, start: usize, end: usize },
+    Branch { bounds: Aabb, left: usize, right: usize },
+}
+
+// Bounding volume hierarchy over primitives that are only known by index and bounds.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(bounds.len() * 2),
+            indices: (0..bounds.len()).collect(),
+        };
+
+        if !bounds.is_empty() {
+            let centroids: Vec<Vec3> = bounds.iter().map(|aabb| aabb.centroid()).collect();
+            bvh.build(0, bounds.len(), bounds, &centroids);
+        }
+
+        bvh
+    }
+
+    // Median split along the axis with the widest spread of centroids.
+    fn build(&mut self, start: usize, end: usize, bounds: &[Aabb], centroids: &[Vec3]) -> usize {
+        let node_bounds = self.indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |aabb, &index| aabb.union(&bounds[index]));
+        let node_index = self.nodes.len();
+
+        let mut spread = Aabb::empty();
+        for &index in &self.indices[start..end] {
+            spread.grow(&centroids[index]);
+        }
+        let extent = spread.max - spread.min;
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].partial_cmp(&extent[b]).unwrap())
+            .unwrap();
+
+        if end - start <= MAX_LEAF_SIZE || extent[axis] <= 0. {
+            self.nodes.push(Node::Leaf {
+                bounds: node_bounds,
+                start,
+                end,
+            });
+            return node_index;
+        }
+
+        self.indices[start..end]
+            .sort_by(|&a, &b| centroids[a][axis].partial_cmp(&centroids[b][axis]).unwrap());
+        let middle = (start + end) / 2;
+
+        // Reserve the slot so children are laid out after their parent.
+        self.nodes.push(Node::Leaf {
+            bounds: Aabb::empty(),
+            start,
+            end,
+        });
+        let left = self.build(start, middle, bounds, centroids);
+        let right = self.build(middle, end, bounds, centroids);
+        self.nodes[node_index] = Node::Branch {
+            bounds: node_bounds,
+            left,
+            right,
+        };
+
+        node_index
+    }
+
+    // Calls `visit` with every primitive whose bounds the ray may reach before `t_max`.
+    // `visit` returns the distance of a hit, which then limits the remaining traversal.
+    pub fn traverse<F>(&self, origin: &Vec3, direction: &Vec3, t_max: Float, mut visit: F)
+    where
+        F: FnMut(usize, Float) -> Option<Float>,
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut t_max = t_max;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            match &self.nodes[node_index] {
+                Node::Leaf { bounds, start, end } => {
+                    if bounds.ray_interval(origin, direction, t_max).is_none() {
+                        continue;
+                    }
+
+                    for &index in &self.indices[*start..*end] {
+                        if let Some(t) = visit(index, t_max) {
+                            t_max = t_max.min(t);
+                        }
+                    }
+                }
+                Node::Branch {
+                    bounds,
+                    left,
+                    right,
+                } => {
+                    if bounds.ray_interval(origin, direction, t_max).is_some() {
+                        stack.push(*right);
+                        stack.push(*left);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::aabb::Aabb;
+    use crate::geometry::bvh::Bvh;
+    use crate::geometry::vec3::Vec3;
+
+    #[test]
+    fn traverse() {
+        let bounds: Vec<Aabb> = (0..20)
+            .map(|i| {
+                let x = i as Float * 3.;
+                Aabb::new(Vec3::new(x, -1., -1.), Vec3::new(x + 1., 1., 1.))
+            })
+            .collect();
+        let bvh = Bvh::new(&bounds);
+        let mut visited = Vec::new();
+
+        bvh.traverse(
+            &Vec3::new(30.5, 10., 0.),
+            &Vec3::new(0., -1., 0.),
+            Float::MAX,
+            |index, _| {
+                visited.push(index);
+                None
+            },
+        );
+
+        assert!(visited.contains(&10));
+        assert!(visited.len() <= 4);
+    }
+}
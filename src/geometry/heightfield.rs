@@ -0,0 +1,327 @@
+use super::Float;
+use super::aabb::Aabb;
+use super::material::Material;
+use super::object::{Hit, Object};
+use super::ray::Ray;
+use super::triangle;
+use super::vec3::Vec3;
+
+// A regular grid of height samples. Grid cell (x, z) spans `scale.x` by `scale.z` world units
+// starting at `origin`, and a sample of 1.0 lifts the surface `scale.y` units above it.
+pub struct Heightfield {
+    pub origin: Vec3,
+    pub scale: Vec3,
+    pub material: Box<dyn Material>,
+
+    width: usize,
+    depth: usize,
+    heights: Vec<Float>,
+    normals: Vec<Vec3>,
+    cell_bounds: Vec<(Float, Float)>,
+    bounds: Aabb,
+}
+
+impl Heightfield {
+    pub fn new(
+        width: usize,
+        depth: usize,
+        heights: Vec<Float>,
+        origin: Vec3,
+        scale: Vec3,
+        material: Box<dyn Material>,
+    ) -> Self {
+        assert!(width >= 2 && depth >= 2, "heightfield needs at least 2x2 samples");
+        assert_eq!(heights.len(), width * depth);
+
+        let mut heightfield = Self {
+            origin,
+            scale,
+            material,
+            width,
+            depth,
+            heights,
+            normals: Vec::with_capacity(width * depth),
+            cell_bounds: Vec::with_capacity((width - 1) * (depth - 1)),
+            bounds: Aabb::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.)),
+        };
+
+        heightfield.compute_normals();
+        heightfield.compute_bounds();
+
+        heightfield
+    }
+
+    // Loads a grayscale image where black is the lowest and white the highest point.
+    pub fn from_image(
+        path: &str,
+        origin: Vec3,
+        scale: Vec3,
+        material: Box<dyn Material>,
+    ) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Can't load heightfield {}: {}", path, e))?
+            .to_luma();
+        let (width, depth) = (image.width() as usize, image.height() as usize);
+
+        if width < 2 || depth < 2 {
+            return Err(format!("Heightfield {} must be at least 2x2 pixels", path));
+        }
+
+        let heights = image.pixels().map(|pixel| pixel[0] as Float / 255.).collect();
+
+        Ok(Self::new(width, depth, heights, origin, scale, material))
+    }
+
+    fn height(&self, x: usize, z: usize) -> Float {
+        self.heights[z * self.width + x]
+    }
+
+    fn vertex(&self, x: usize, z: usize) -> Vec3 {
+        Vec3::new(x as Float, self.height(x, z), z as Float)
+    }
+
+    fn compute_normals(&mut self) {
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                let (left, right) = (x.saturating_sub(1), usize::min(x + 1, self.width - 1));
+                let (back, front) = (z.saturating_sub(1), usize::min(z + 1, self.depth - 1));
+
+                let dx = (self.height(right, z) - self.height(left, z)) / (right - left) as Float;
+                let dz = (self.height(x, front) - self.height(x, back)) / (front - back) as Float;
+
+                self.normals.push(Vec3::new(-dx, 1., -dz).normalize());
+            }
+        }
+    }
+
+    fn compute_bounds(&mut self) {
+        let mut min_height = Float::MAX;
+        let mut max_height = Float::MIN;
+
+        for z in 0..self.depth - 1 {
+            for x in 0..self.width - 1 {
+                let corners = [
+                    self.height(x, z),
+                    self.height(x + 1, z),
+                    self.height(x, z + 1),
+                    self.height(x + 1, z + 1),
+                ];
+                let low = corners.iter().cloned().fold(Float::MAX, Float::min);
+                let high = corners.iter().cloned().fold(Float::MIN, Float::max);
+
+                min_height = min_height.min(low);
+                max_height = max_height.max(high);
+                self.cell_bounds.push((low, high));
+            }
+        }
+
+        self.bounds = Aabb::new(
+            Vec3::new(0., min_height, 0.),
+            Vec3::new((self.width - 1) as Float, max_height, (self.depth - 1) as Float),
+        );
+    }
+
+    // Tests both triangles of a cell and returns the closest distance with the interpolated normal.
+    fn intersect_cell(
+        &self,
+        x: usize,
+        z: usize,
+        origin: &Vec3,
+        direction: &Vec3,
+        t_max: Float,
+    ) -> Option<(Float, Vec3)> {
+        let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let triangles = [[0, 1, 2], [0, 2, 3]];
+        let mut closest: Option<(Float, Vec3)> = None;
+
+        for indices in triangles.iter() {
+            let [a, b, c] = [corners[indices[0]], corners[indices[1]], corners[indices[2]]];
+            let hit = triangle::ray_intersect(
+                origin,
+                direction,
+                &self.vertex(a.0, a.1),
+                &self.vertex(b.0, b.1),
+                &self.vertex(c.0, c.1),
+            );
+
+            if let Some((t, u, v)) = hit {
+                let limit = closest.as_ref().map_or(t_max, |hit| hit.0);
+                if t >= limit {
+                    continue;
+                }
+
+                let normal = self.normals[a.1 * self.width + a.0] * (1. - u - v)
+                    + self.normals[b.1 * self.width + b.0] * u
+                    + self.normals[c.1 * self.width + c.0] * v;
+                closest = Some((t, normal));
+            }
+        }
+
+        closest
+    }
+}
+
+impl Object for Heightfield {
+    // Walks the cells under the ray with a 2D DDA, skipping cells whose height range the ray
+    // passes above or below.
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        // Mapping both origin and direction keeps `t` identical in local and world space.
+        let origin = (ray.origin - self.origin) / self.scale;
+        let direction = ray.direction / self.scale;
+        let (t_enter, t_exit) = self.bounds.ray_interval(&origin, &direction, ray.t)?;
+
+        let cells_x = (self.width - 1) as isize;
+        let cells_z = (self.depth - 1) as isize;
+        let entry = origin + direction * t_enter;
+        let mut x = (entry.x.floor() as isize).max(0).min(cells_x - 1);
+        let mut z = (entry.z.floor() as isize).max(0).min(cells_z - 1);
+
+        let step_x = if direction.x < 0. { -1 } else { 1 };
+        let step_z = if direction.z < 0. { -1 } else { 1 };
+        let delta_x = (1. / direction.x).abs();
+        let delta_z = (1. / direction.z).abs();
+        let border_x = if step_x > 0 { x + 1 } else { x };
+        let border_z = if step_z > 0 { z + 1 } else { z };
+        let mut next_x = if direction.x == 0. {
+            Float::MAX
+        } else {
+            (border_x as Float - origin.x) / direction.x
+        };
+        let mut next_z = if direction.z == 0. {
+            Float::MAX
+        } else {
+            (border_z as Float - origin.z) / direction.z
+        };
+
+        let mut t = t_enter;
+
+        loop {
+            let cell_exit = next_x.min(next_z).min(t_exit);
+            let y0 = origin.y + direction.y * t;
+            let y1 = origin.y + direction.y * cell_exit;
+            let (low, high) = self.cell_bounds[(z * cells_x + x) as usize];
+
+            if y0.max(y1) >= low && y0.min(y1) <= high {
+                let hit = self.intersect_cell(x as usize, z as usize, &origin, &direction, ray.t);
+
+                if let Some((hit_t, normal)) = hit {
+                    ray.t = hit_t;
+
+                    let point = ray.origin + ray.direction * hit_t;
+                    let normal = (normal / self.scale).normalize();
+                    let (tangent, bitangent) = normal.orthonormal_basis();
+
+                    return Some(Hit {
+                        point,
+                        local_point: point - self.origin,
+                        normal,
+                        tangent,
+                        bitangent,
+                        color: None,
+                        uv: None,
+                        primitive: 0,
+                    });
+                }
+            }
+
+            if cell_exit >= t_exit {
+                return None;
+            }
+
+            if next_x < next_z {
+                x += step_x;
+                t = next_x;
+                next_x += delta_x;
+            } else {
+                z += step_z;
+                t = next_z;
+                next_z += delta_z;
+            }
+
+            if x < 0 || x >= cells_x || z < 0 || z >= cells_z {
+                return None;
+            }
+        }
+    }
+
+    fn material(&self, _hit: &Hit) -> &dyn Material {
+        self.material.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::heightfield::Heightfield;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::Material;
+    use crate::geometry::object::Object;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::rgb::RGB;
+
+    fn material() -> Box<dyn Material> {
+        Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        })
+    }
+
+    #[test]
+    fn intersect_flat() {
+        let heightfield = Heightfield::new(
+            3,
+            3,
+            vec![0.5; 9],
+            Vec3::new(-10., 0., -10.),
+            Vec3::new(10., 2., 10.),
+            material(),
+        );
+        let mut ray = Ray::new(
+            Vec3::new(3., 5., 7.),
+            Vec3::new(0., -1., 0.),
+            Float::MAX,
+        );
+
+        let hit = heightfield.intersect(&mut ray).unwrap();
+
+        assert_approx_eq!(ray.t, 4.);
+        assert_approx_eq!(hit.normal, Vec3::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn intersect_slope() {
+        // Rises by one scaled unit per cell along x, so the surface is the plane y = x.
+        let heights = vec![0., 0.5, 1., 0., 0.5, 1.];
+        let heightfield = Heightfield::new(
+            3,
+            2,
+            heights,
+            Vec3::new(0., 0., 0.),
+            Vec3::new(1., 2., 1.),
+            material(),
+        );
+        let mut ray = Ray::new(
+            Vec3::new(-5., 1.5, 0.5),
+            Vec3::new(1., 0., 0.),
+            Float::MAX,
+        );
+
+        let hit = heightfield.intersect(&mut ray).unwrap();
+
+        let diagonal = Float::sqrt(0.5);
+        assert_approx_eq!(hit.point, Vec3::new(1.5, 1.5, 0.5));
+        assert_approx_eq!(hit.normal, Vec3::new(-diagonal, diagonal, 0.));
+
+        let mut above = Ray::new(
+            Vec3::new(-5., 2.5, 0.5),
+            Vec3::new(1., 0., 0.),
+            Float::MAX,
+        );
+        assert!(heightfield.intersect(&mut above).is_none());
+    }
+}
@@ -0,0 +1,257 @@
+use super::subsurface::Medium;
+use super::{microfacet, pick_lobe, scattered, Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+// A glossy transparent layer over another material, e.g. the lacquer on car paint or varnished
+// wood. The coat reflects its Fresnel share of the light, the base only gets what passes the
+// coat on the way in and again on the way out, tinted along the way.
+pub struct Clearcoat {
+    pub base: Box<dyn Material>,
+    pub ior: Float,
+    pub roughness: Float,
+    // Color light takes on going straight through a coat of thickness 1 and back out.
+    pub tint: RGB,
+    pub thickness: Float,
+}
+
+impl Clearcoat {
+    fn fresnel(&self, cos: Float) -> Float {
+        let reflectance = ((self.ior - 1.) / (self.ior + 1.)).powi(2);
+
+        reflectance + (1. - reflectance) * (1. - cos.clamp(0., 1.)).powi(5)
+    }
+
+    // Share of the light left after crossing the coat at the angles of the viewer and, for
+    // light reflected by the base, of the light. Slanted paths are longer inside the coat.
+    fn absorption(&self, cos_view: Float, cos_light: Option<Float>) -> Vec3 {
+        let inside = |cos: Float| (1. - (1. - cos * cos) / (self.ior * self.ior)).sqrt();
+        let crossings = match cos_light {
+            Some(cos_light) => 1. / inside(cos_view) + 1. / inside(cos_light),
+            None => 1. / inside(cos_view),
+        };
+        let depth = self.thickness * crossings / 2.;
+        let tint = self.tint.as_vector() / 255.;
+
+        Vec3::new(tint.x.powf(depth), tint.y.powf(depth), tint.z.powf(depth))
+    }
+
+    // Light passing the coat both ways, reaching the base and back to the viewer.
+    fn passed(&self, normal: &Vec3, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        let cos_view = normal.dot_product(to_viewer).abs();
+        let cos_light = normal.dot_product(to_light);
+
+        if cos_light > 0. {
+            self.absorption(cos_view, Some(cos_light))
+                * ((1. - self.fresnel(cos_view)) * (1. - self.fresnel(cos_light)))
+        } else {
+            self.absorption(cos_view, None) * (1. - self.fresnel(cos_view))
+        }
+    }
+
+    // Probability of sampling the coat rather than the base.
+    fn coat_weight(&self, surface: &Surface, to_viewer: &Vec3) -> Float {
+        self.fresnel(surface.facing(to_viewer).dot_product(to_viewer))
+    }
+}
+
+impl Material for Clearcoat {
+    fn color(&self) -> RGB {
+        self.base.color()
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        self.base.texture_color(surface)
+    }
+
+    fn shading_normal(&self, surface: &Surface) -> Option<Vec3> {
+        self.base.shading_normal(surface)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.base.emission()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.base.dispersive()
+    }
+
+    fn ior(&self) -> Float {
+        self.ior
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        self.base.subsurface()
+    }
+
+    fn cut_out(&self, surface: &Surface) -> bool {
+        self.base.cut_out(surface)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        let normal = surface.facing(to_viewer);
+        let reflectance = Vec3::new(1., 1., 1.) * self.fresnel(1.);
+        let coat = microfacet::evaluate(
+            &reflectance,
+            microfacet::alpha(self.roughness),
+            &normal,
+            to_viewer,
+            to_light,
+        );
+
+        coat + self.base.evaluate(surface, to_viewer, to_light)
+            * self.passed(&normal, to_viewer, to_light)
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let coat = self.coat_weight(surface, to_viewer);
+        let (lobe, probability, u) = pick_lobe(&[coat, 1. - coat], random.0)?;
+        let normal = surface.facing(to_viewer);
+
+        let direction = if lobe == 0 {
+            microfacet::sample(
+                microfacet::alpha(self.roughness),
+                &normal,
+                to_viewer,
+                (u, random.1),
+            )
+        } else {
+            let sample = self.base.sample(surface, to_viewer, (u, random.1))?;
+            if self.base.pdf(surface, to_viewer, &sample.direction) <= 0. {
+                return Some(Sample {
+                    weight: sample.weight * self.passed(&normal, to_viewer, &sample.direction)
+                        / probability,
+                    direction: sample.direction,
+                });
+            }
+            sample.direction
+        };
+
+        scattered(self, surface, to_viewer, direction)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        let coat = self.coat_weight(surface, to_viewer);
+        let normal = surface.facing(to_viewer);
+
+        coat * microfacet::pdf(
+            microfacet::alpha(self.roughness),
+            &normal,
+            to_viewer,
+            to_light,
+        ) + (1. - coat) * self.base.pdf(surface, to_viewer, to_light)
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let normal = surface.facing(to_viewer);
+        let mut lobes: Vec<Sample> = self
+            .base
+            .specular(surface, to_viewer)
+            .into_iter()
+            .map(|lobe| Sample {
+                weight: lobe.weight * self.passed(&normal, to_viewer, &lobe.direction),
+                direction: lobe.direction,
+            })
+            .collect();
+
+        let reflectance = Vec3::new(1., 1., 1.) * self.fresnel(1.);
+        let mirror = microfacet::mirror(&reflectance, self.roughness, surface, to_viewer);
+        if mirror != Vec3::zero() {
+            lobes.insert(
+                0,
+                Sample {
+                    direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+                    weight: mirror,
+                },
+            );
+        }
+
+        lobes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::clearcoat::Clearcoat;
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::{Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::random::Random;
+    use crate::utils::rgb::RGB;
+
+    fn lacquered(tint: RGB, thickness: Float) -> Clearcoat {
+        Clearcoat {
+            base: Box::new(Lambertian {
+                color: RGB::new(255, 255, 255),
+                color_texture: None,
+            }),
+            ior: 1.5,
+            roughness: 0.1,
+            tint,
+            thickness,
+        }
+    }
+
+    fn surface() -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color: Vec3::new(1., 1., 1.),
+            uv: None,
+            footprint: 0.,
+            point: Vec3::zero(),
+            local_point: Vec3::zero(),
+            wavelength: None,
+        }
+    }
+
+    #[test]
+    fn coat_and_base_share_the_light() {
+        // A white base under a clear coat sends back nearly all light, split between both.
+        let material = lacquered(RGB::new(255, 255, 255), 1.);
+        let surface = surface();
+        let to_viewer = Vec3::new(1., 2., 0.).normalize();
+        let mut random = Random::new(5);
+        let count = 20_000;
+
+        let mut total = Vec3::zero();
+        for _ in 0..count {
+            if let Some(sample) = material.sample(&surface, &to_viewer, random.pair()) {
+                let pdf = material.pdf(&surface, &to_viewer, &sample.direction);
+                let value = material.evaluate(&surface, &to_viewer, &sample.direction);
+                assert_approx_eq!(sample.weight * pdf, value, 1e-3);
+                total += sample.weight;
+            }
+        }
+        let total = total / count as Float;
+
+        assert!(total.x > 0.85 && total.x <= 1.02, "{:?}", total);
+        assert!(material.specular(&surface, &to_viewer)[0].weight.x > 0.03);
+    }
+
+    #[test]
+    fn thicker_coats_tint_more() {
+        let to_viewer = Vec3::new(0., 1., 0.);
+        let to_light = Vec3::new(1., 1., 0.).normalize();
+        let base = |thickness| {
+            lacquered(RGB::new(255, 200, 100), thickness).evaluate(
+                &surface(),
+                &to_viewer,
+                &to_light,
+            )
+        };
+
+        let (thin, thick) = (base(0.5), base(2.));
+        assert!(thin.x > thin.y && thin.y > thin.z);
+        assert!(thick.z < thin.z && thick.y < thin.y);
+        assert_approx_eq!(thick.x, thin.x, 1e-4);
+    }
+}
@@ -0,0 +1,92 @@
+use super::texture::Texture;
+use super::{microfacet, scattered, Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Metal with GGX microfacets, its color is the reflectance at normal incidence.
+pub struct Conductor {
+    pub color: RGB,
+    pub roughness: Float,
+    // Multiplies `color`.
+    pub color_texture: Option<Arc<dyn Texture>>,
+    // Multiplies `roughness` with its green channel, as in glTF.
+    pub roughness_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Conductor {
+    fn roughness(&self, surface: &Surface) -> Float {
+        surface
+            .lookup(&self.roughness_texture)
+            .map_or(self.roughness, |texel| self.roughness * texel.y)
+    }
+}
+
+impl Material for Conductor {
+    fn color(&self) -> RGB {
+        self.color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.color_texture)? * self.color.as_vector() / 255.)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        let normal = surface.facing(to_viewer);
+
+        microfacet::evaluate(
+            &surface.color,
+            microfacet::alpha(self.roughness(surface)),
+            &normal,
+            to_viewer,
+            to_light,
+        )
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let normal = surface.facing(to_viewer);
+        let direction = microfacet::sample(
+            microfacet::alpha(self.roughness(surface)),
+            &normal,
+            to_viewer,
+            random,
+        );
+
+        scattered(self, surface, to_viewer, direction)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        let normal = surface.facing(to_viewer);
+
+        microfacet::pdf(
+            microfacet::alpha(self.roughness(surface)),
+            &normal,
+            to_viewer,
+            to_light,
+        )
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let weight = microfacet::mirror(
+            &surface.color,
+            self.roughness(surface),
+            surface,
+            to_viewer,
+        );
+        if weight == Vec3::zero() {
+            return Vec::new();
+        }
+
+        vec![Sample {
+            direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+            weight,
+        }]
+    }
+}
@@ -0,0 +1,78 @@
+use super::subsurface::Medium;
+use super::texture::Texture;
+use super::{average, Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Any material with holes in it, e.g. leaves or a fence drawn on a flat quad. Rays pass through
+// where the opacity is below the cutoff as if the surface weren't there, without bending.
+pub struct Cutout {
+    pub material: Box<dyn Material>,
+    pub opacity: Float,
+    // Multiplies `opacity` by its brightness, e.g. the alpha channel of a leaf's image.
+    pub opacity_texture: Option<Arc<dyn Texture>>,
+    pub cutoff: Float,
+}
+
+impl Material for Cutout {
+    fn color(&self) -> RGB {
+        self.material.color()
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.texture_color(surface)
+    }
+
+    fn shading_normal(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.shading_normal(surface)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.material.emission()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn ior(&self) -> Float {
+        self.material.ior()
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        self.material.subsurface()
+    }
+
+    fn cut_out(&self, surface: &Surface) -> bool {
+        let opacity = match surface.lookup(&self.opacity_texture) {
+            Some(texel) => self.opacity * average(&texel),
+            None => self.opacity,
+        };
+
+        opacity < self.cutoff || self.material.cut_out(surface)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        self.material.evaluate(surface, to_viewer, to_light)
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        self.material.sample(surface, to_viewer, random)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        self.material.pdf(surface, to_viewer, to_light)
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        self.material.specular(surface, to_viewer)
+    }
+}
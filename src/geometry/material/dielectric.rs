@@ -0,0 +1,122 @@
+use super::dispersion::{Dispersion, D_LINE};
+use super::texture::Texture;
+use super::{Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Clear glass or water: reflects and refracts by Fresnel's equations, the color tints the
+// refracted light. The normal points out of the medium.
+pub struct Dielectric {
+    pub color: RGB,
+    // Ignored when the material has a dispersion.
+    pub ior: Float,
+    pub dispersion: Option<Dispersion>,
+    // Multiplies `color`.
+    pub color_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Dielectric {
+    // For the wavelength of the ray, or the middle of the spectrum for rays carrying all of it.
+    fn ior_at(&self, surface: &Surface) -> Float {
+        match self.dispersion {
+            Some(dispersion) => dispersion.ior(surface.wavelength.unwrap_or(D_LINE)),
+            None => self.ior,
+        }
+    }
+
+    // Reflected direction with its reflectance, then the refracted one unless light is
+    // reflected totally.
+    fn split(&self, surface: &Surface, to_viewer: &Vec3) -> (Vec3, Float, Option<Vec3>) {
+        let normal = surface.facing(to_viewer);
+        let entering = normal == surface.normal;
+        let ior = self.ior_at(surface);
+        let eta = if entering { 1. / ior } else { ior };
+
+        let cos_i = normal.dot_product(to_viewer).min(1.);
+        let reflected = (-*to_viewer).reflect(&normal).normalize();
+        let sin2_t = eta * eta * (1. - cos_i * cos_i);
+        if sin2_t >= 1. {
+            return (reflected, 1., None);
+        }
+
+        let cos_t = (1. - sin2_t).sqrt();
+        let refracted = (-*to_viewer * eta + normal * (eta * cos_i - cos_t)).normalize();
+
+        // Average of both polarizations.
+        let parallel = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+        let perpendicular = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+        let reflectance = (parallel * parallel + perpendicular * perpendicular) / 2.;
+
+        (reflected, reflectance, Some(refracted))
+    }
+}
+
+impl Material for Dielectric {
+    fn color(&self) -> RGB {
+        self.color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.color_texture)? * self.color.as_vector() / 255.)
+    }
+
+    fn dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
+    fn ior(&self) -> Float {
+        match self.dispersion {
+            Some(dispersion) => dispersion.ior(D_LINE),
+            None => self.ior,
+        }
+    }
+
+    fn evaluate(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let (reflected, reflectance, refracted) = self.split(surface, to_viewer);
+
+        // Picking by reflectance leaves the weights at one, up to the tint.
+        match refracted {
+            Some(refracted) if random.0 >= reflectance => Some(Sample {
+                direction: refracted,
+                weight: surface.color,
+            }),
+            _ => Some(Sample {
+                direction: reflected,
+                weight: Vec3::new(1., 1., 1.),
+            }),
+        }
+    }
+
+    fn pdf(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Float {
+        0.
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let (reflected, reflectance, refracted) = self.split(surface, to_viewer);
+        let mut lobes = vec![Sample {
+            direction: reflected,
+            weight: Vec3::new(reflectance, reflectance, reflectance),
+        }];
+
+        if let Some(refracted) = refracted {
+            lobes.push(Sample {
+                direction: refracted,
+                weight: surface.color * (1. - reflectance),
+            });
+        }
+
+        lobes
+    }
+}
@@ -0,0 +1,75 @@
+use crate::geometry::Float;
+
+// Fraunhofer lines used to specify glasses, in nanometers: yellow helium d, blue hydrogen F and
+// red hydrogen C.
+pub const D_LINE: Float = 587.56;
+const F_LINE: Float = 486.13;
+const C_LINE: Float = 656.27;
+
+// How the index of refraction changes with the wavelength, which splits white light into
+// rainbows. Wavelengths in the formulas are in micrometers, as in glass catalogs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    // n = a + b / λ², good enough across the visible spectrum.
+    Cauchy { a: Float, b: Float },
+    // n² = 1 + Σ bᵢ λ² / (λ² - cᵢ).
+    Sellmeier { b: [Float; 3], c: [Float; 3] },
+}
+
+impl Dispersion {
+    // From the index at the d line and the Abbe number, the lower the number the stronger the
+    // dispersion: about 64 for crown glass, 55 for water and 30 for lead crystal.
+    pub fn from_abbe(ior: Float, abbe: Float) -> Self {
+        let inverse_sqr = |wavelength: Float| 1e6 / (wavelength * wavelength);
+        let b = (ior - 1.) / (abbe * (inverse_sqr(F_LINE) - inverse_sqr(C_LINE)));
+
+        Dispersion::Cauchy {
+            a: ior - b * inverse_sqr(D_LINE),
+            b,
+        }
+    }
+
+    pub fn ior(&self, wavelength: Float) -> Float {
+        let micrometers_sqr = (wavelength / 1000.).powi(2);
+
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / micrometers_sqr,
+            Dispersion::Sellmeier { b, c } => {
+                let sum = (0..3).fold(0., |sum, i| {
+                    sum + b[i] * micrometers_sqr / (micrometers_sqr - c[i])
+                });
+
+                (1. + sum).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::dispersion::{Dispersion, C_LINE, D_LINE, F_LINE};
+    use crate::geometry::vec3::assert_approx_eq;
+
+    #[test]
+    fn abbe_number() {
+        let crown = Dispersion::from_abbe(1.5168, 64.17);
+
+        assert_approx_eq!(crown.ior(D_LINE), 1.5168);
+        assert_approx_eq!(
+            (crown.ior(D_LINE) - 1.) / (crown.ior(F_LINE) - crown.ior(C_LINE)),
+            64.17,
+            1e-2
+        );
+    }
+
+    #[test]
+    fn sellmeier() {
+        let diamond = Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.],
+            c: [0.030625, 0.011236, 0.],
+        };
+
+        assert_approx_eq!(diamond.ior(D_LINE), 2.417, 1e-3);
+        assert!(diamond.ior(450.) > diamond.ior(650.));
+    }
+}
@@ -0,0 +1,68 @@
+use super::subsurface::Medium;
+use super::{Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+// Any material glowing on top of reflecting light, e.g. a neon tube or a lightbulb.
+pub struct Emissive {
+    pub material: Box<dyn Material>,
+    pub color: RGB,
+    // Multiplies `color`, above 1 for lights bright enough to light up their surroundings.
+    pub strength: Float,
+}
+
+impl Material for Emissive {
+    fn color(&self) -> RGB {
+        self.material.color()
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.texture_color(surface)
+    }
+
+    fn shading_normal(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.shading_normal(surface)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.material.emission() + self.color.as_vector() / 255. * self.strength
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn ior(&self) -> Float {
+        self.material.ior()
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        self.material.subsurface()
+    }
+
+    fn cut_out(&self, surface: &Surface) -> bool {
+        self.material.cut_out(surface)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        self.material.evaluate(surface, to_viewer, to_light)
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        self.material.sample(surface, to_viewer, random)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        self.material.pdf(surface, to_viewer, to_light)
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        self.material.specular(surface, to_viewer)
+    }
+}
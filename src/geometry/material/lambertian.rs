@@ -0,0 +1,85 @@
+use super::texture::Texture;
+use super::{cosine_direction, cosine_pdf, scattered, Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Ideal matte surface, scatters light evenly in all directions.
+pub struct Lambertian {
+    pub color: RGB,
+    // Multiplies `color`.
+    pub color_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Material for Lambertian {
+    fn color(&self) -> RGB {
+        self.color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.color_texture)? * self.color.as_vector() / 255.)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        surface.color * cosine_pdf(&surface.facing(to_viewer), to_light)
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let direction = cosine_direction(&surface.facing(to_viewer), random);
+
+        scattered(self, surface, to_viewer, direction)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        cosine_pdf(&surface.facing(to_viewer), to_light)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::consts::PI;
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::{Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::rgb::RGB;
+
+    #[test]
+    fn evaluate() {
+        let white = Lambertian {
+            color: RGB::new(255, 255, 255),
+            color_texture: None,
+        };
+        let surface = Surface {
+            normal: Vec3::new(0., 0., 1.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 1., 0.),
+            color: Vec3::new(1., 1., 1.),
+            uv: None,
+            footprint: 0.,
+            point: Vec3::zero(),
+            local_point: Vec3::zero(),
+            wavelength: None,
+        };
+        let to_viewer = Vec3::new(0., 0.6, 0.8);
+
+        assert_approx_eq!(
+            white.evaluate(&surface, &to_viewer, &Vec3::new(0., 0., 1.)),
+            Vec3::new(1., 1., 1.) / PI
+        );
+        assert_approx_eq!(
+            white.evaluate(&surface, &to_viewer, &Vec3::new(0.8, 0., 0.6)),
+            Vec3::new(0.6, 0.6, 0.6) / PI
+        );
+        assert_eq!(
+            white.evaluate(&surface, &to_viewer, &Vec3::new(0., 0., -1.)),
+            Vec3::zero()
+        );
+    }
+}
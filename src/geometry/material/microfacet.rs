@@ -0,0 +1,142 @@
+// GGX microfacet reflection shared by the conductor and metallic-roughness materials.
+use super::{to_world, Surface};
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+// Below this highlights of point lights shrink to single pixels.
+const MIN_ROUGHNESS: Float = 0.05;
+
+pub fn alpha(roughness: Float) -> Float {
+    roughness.clamp(MIN_ROUGHNESS, 1.).powi(2)
+}
+
+// Cook-Torrance reflection with cosine, `reflectance` being the one at normal incidence.
+pub fn evaluate(
+    reflectance: &Vec3,
+    alpha: Float,
+    normal: &Vec3,
+    to_viewer: &Vec3,
+    to_light: &Vec3,
+) -> Vec3 {
+    let cos_view = normal.dot_product(to_viewer);
+    let cos_light = normal.dot_product(to_light);
+    if cos_view <= 0. || cos_light <= 0. {
+        return Vec3::zero();
+    }
+
+    let half = (*to_viewer + *to_light).normalize();
+
+    schlick(reflectance, half.dot_product(to_viewer))
+        * (ggx(normal.dot_product(&half), alpha) * smith(cos_view, cos_light, alpha)
+            / (4. * cos_view))
+}
+
+// Reflects the viewer around a microfacet normal drawn in proportion to its visible area.
+pub fn sample(alpha: Float, normal: &Vec3, to_viewer: &Vec3, random: (Float, Float)) -> Vec3 {
+    let cos = ((1. - random.0) / (1. + (alpha * alpha - 1.) * random.0)).sqrt();
+    let sin = (1. - cos * cos).max(0.).sqrt();
+    let angle = 2. * PI * random.1;
+    let half = to_world(
+        &Vec3::new(sin * angle.cos(), sin * angle.sin(), cos),
+        normal,
+    );
+
+    (-*to_viewer).reflect(&half)
+}
+
+pub fn pdf(alpha: Float, normal: &Vec3, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+    if normal.dot_product(to_light) <= 0. {
+        return 0.;
+    }
+
+    let half = (*to_viewer + *to_light).normalize();
+    let cos_half = normal.dot_product(&half);
+
+    ggx(cos_half, alpha) * cos_half / (4. * half.dot_product(to_viewer).abs())
+}
+
+// The part of the reflection that a single mirror ray stands in for. Only smooth surfaces
+// reflect a recognizable image, rough ones get their highlights from `evaluate` alone.
+pub fn mirror(reflectance: &Vec3, roughness: Float, surface: &Surface, to_viewer: &Vec3) -> Vec3 {
+    let smoothness = 1. - roughness.clamp(0., 1.);
+    let cos_view = surface.facing(to_viewer).dot_product(to_viewer);
+
+    schlick(reflectance, cos_view) * smoothness.powi(2)
+}
+
+// Share of microfacets facing along the half vector.
+pub fn ggx(cos_half: Float, alpha: Float) -> Float {
+    let alpha2 = alpha * alpha;
+    let denominator = cos_half * cos_half * (alpha2 - 1.) + 1.;
+
+    alpha2 / (PI * denominator * denominator)
+}
+
+// Share of microfacets neither shadowed nor masked by others, separable Schlick-GGX form.
+fn smith(cos_view: Float, cos_light: Float, alpha: Float) -> Float {
+    let k = alpha / 2.;
+    let g1 = |cos: Float| cos / (cos * (1. - k) + k);
+
+    g1(cos_view) * g1(cos_light)
+}
+
+pub fn schlick(reflectance: &Vec3, cos: Float) -> Vec3 {
+    let weight = (1. - cos.clamp(0., 1.)).powi(5);
+
+    *reflectance + (Vec3::new(1., 1., 1.) - *reflectance) * weight
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::consts::PI;
+    use crate::geometry::material::microfacet::{ggx, pdf, sample, schlick};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::random::Random;
+
+    #[test]
+    fn ggx_is_normalized() {
+        // The projected microfacet area covers the surface exactly once.
+        for &alpha in &[0.1, 0.5, 1.] {
+            let steps = 100_000;
+            let step = PI / 2. / steps as Float;
+            let area: Float = (0..steps)
+                .map(|i| {
+                    let theta = (i as Float + 0.5) * step;
+                    ggx(theta.cos(), alpha) * theta.cos() * theta.sin() * 2. * PI * step
+                })
+                .sum();
+
+            assert_approx_eq!(area, 1., 1e-3);
+        }
+    }
+
+    #[test]
+    fn schlick_reflectance() {
+        let glass = Vec3::new(0.04, 0.04, 0.04);
+
+        assert_approx_eq!(schlick(&glass, 1.), glass);
+        assert_approx_eq!(schlick(&glass, 0.), Vec3::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn samples_follow_pdf() {
+        // Smoother surfaces concentrate the samples around the mirror direction.
+        let normal = Vec3::new(0., 1., 0.);
+        let to_viewer = Vec3::new(1., 1., 0.).normalize();
+        let mirror = Vec3::new(-1., 1., 0.).normalize();
+        let mut random = Random::new(3);
+
+        for &alpha in &[0.05, 0.5] {
+            let spread = (0..1000)
+                .map(|_| sample(alpha, &normal, &to_viewer, random.pair()))
+                .filter(|direction| pdf(alpha, &normal, &to_viewer, direction) > 0.)
+                .map(|direction| 1. - direction.dot_product(&mirror))
+                .sum::<Float>()
+                / 1000.;
+
+            assert!(spread < alpha);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use super::texture::Texture;
+use super::{Material, Sample, Surface};
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Perfect mirror, tinted by its color.
+pub struct Mirror {
+    pub color: RGB,
+    // Multiplies `color`.
+    pub color_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Material for Mirror {
+    fn color(&self) -> RGB {
+        self.color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.color_texture)? * self.color.as_vector() / 255.)
+    }
+
+    fn evaluate(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        _random: (Float, Float),
+    ) -> Option<Sample> {
+        self.specular(surface, to_viewer).pop()
+    }
+
+    fn pdf(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Float {
+        0.
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        vec![Sample {
+            direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+            weight: surface.color,
+        }]
+    }
+}
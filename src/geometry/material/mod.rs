@@ -0,0 +1,378 @@
+use self::subsurface::Medium;
+use self::texture::Texture;
+use crate::geometry::consts::PI;
+use crate::geometry::object::Uv;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+pub mod clearcoat;
+pub mod conductor;
+pub mod cutout;
+pub mod dielectric;
+pub mod dispersion;
+pub mod emissive;
+pub mod lambertian;
+mod microfacet;
+pub mod mirror;
+pub mod pbr;
+pub mod phong;
+pub mod procedural;
+pub mod relief;
+pub mod subsurface;
+pub mod texture;
+pub mod thin_film;
+
+// What a material needs to know about the shaded point.
+#[derive(Clone, Copy)]
+pub struct Surface {
+    // Shading normal, it may face away from the viewer, e.g. inside a glass sphere.
+    pub normal: Vec3,
+    // Directions of growing u and v, see `Hit`.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    // Base color in 0..1: the material's own or its texture's, unless the object overrides it
+    // with vertex colors.
+    pub color: Vec3,
+    pub uv: Option<Uv>,
+    // Width of the ray that hit the surface, measured across the surface.
+    pub footprint: Float,
+    // Where the surface was hit, in the scene and in the frame of the object.
+    pub point: Vec3,
+    pub local_point: Vec3,
+    // Of the ray that hit the surface, see `Ray::wavelength`.
+    pub wavelength: Option<Float>,
+}
+
+impl Surface {
+    // The normal turned to the side `direction` points to.
+    pub fn facing(&self, direction: &Vec3) -> Vec3 {
+        if self.normal.dot_product(direction) < 0. {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+
+    // Value of `texture` at the shaded point, None without a texture or where it's undefined.
+    pub fn lookup(&self, texture: &Option<Arc<dyn Texture>>) -> Option<Vec3> {
+        texture.as_ref()?.sample(self)
+    }
+}
+
+pub struct Sample {
+    // Where the light arrives from.
+    pub direction: Vec3,
+    // Share of that light reaching the viewer: `evaluate / pdf` for scattered light, the
+    // reflectance or transmittance for specular directions.
+    pub weight: Vec3,
+}
+
+// How a surface scatters light, i.e. its BSDF. Directions point away from the surface, so
+// `to_light` is where the light arrives from. Whitted-style integrators light the surface with
+// `evaluate` and follow `specular` with recursive rays, path tracers continue along `sample`.
+pub trait Material: Send + Sync {
+    fn color(&self) -> RGB;
+    // Base color where the material is textured, in 0..1 like `Surface::color`.
+    fn texture_color(&self, _surface: &Surface) -> Option<Vec3> {
+        None
+    }
+    // Normal to shade with instead of the geometric one, e.g. from a normal map.
+    fn shading_normal(&self, _surface: &Surface) -> Option<Vec3> {
+        None
+    }
+    // Light the surface gives off by itself, towards every direction and in the units of light
+    // intensities. Objects made of a glowing material light the scene.
+    fn emission(&self) -> Vec3 {
+        Vec3::zero()
+    }
+    // Whether light is bent differently by wavelength, in which case rays reaching the surface
+    // are split into bands of the spectrum first.
+    fn dispersive(&self) -> bool {
+        false
+    }
+    // Index of refraction of the material under a coating, where the light reflected off the
+    // coating's bottom comes from. Most non-metals are close to the default.
+    fn ior(&self) -> Float {
+        1.5
+    }
+    // Medium below the surface that light entering it scatters in, e.g. for wax or skin.
+    fn subsurface(&self) -> Option<Medium> {
+        None
+    }
+    // Whether rays pass through the surface at this point as if it weren't there.
+    fn cut_out(&self, _surface: &Surface) -> bool {
+        false
+    }
+    // Light scattered towards the viewer per unit of light arriving along `to_light`, cosine
+    // included. Specular directions carry no share of it.
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3;
+    // Picks where the light comes from with two uniform numbers in 0..1, importance sampling the
+    // material. None when the path ends, e.g. at a black surface.
+    fn sample(&self, surface: &Surface, to_viewer: &Vec3, random: (Float, Float))
+        -> Option<Sample>;
+    // Density of `sample` picking `to_light`, per solid angle. Specular directions aren't counted.
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float;
+    // Mirror-like directions for integrators that trace them explicitly, glossy materials may
+    // approximate their reflections with one.
+    fn specular(&self, _surface: &Surface, _to_viewer: &Vec3) -> Vec<Sample> {
+        Vec::new()
+    }
+}
+
+// A sample towards `direction` weighted by the material's own `evaluate` and `pdf`.
+fn scattered<M: Material>(
+    material: &M,
+    surface: &Surface,
+    to_viewer: &Vec3,
+    direction: Vec3,
+) -> Option<Sample> {
+    let pdf = material.pdf(surface, to_viewer, &direction);
+    if pdf <= 0. {
+        return None;
+    }
+
+    Some(Sample {
+        weight: material.evaluate(surface, to_viewer, &direction) / pdf,
+        direction,
+    })
+}
+
+// One of several lobes, chosen with a probability proportional to its weight. Returns the
+// lobe, its probability and `u` stretched back to 0..1 for sampling inside the lobe.
+fn pick_lobe(weights: &[Float], u: Float) -> Option<(usize, Float, Float)> {
+    let total: Float = weights.iter().sum();
+    if total <= 0. {
+        return None;
+    }
+
+    let mut start = 0.;
+    let mut picked = None;
+    for (index, &weight) in weights.iter().enumerate() {
+        if weight <= 0. {
+            continue;
+        }
+
+        let probability = weight / total;
+        picked = Some((index, probability, start));
+        if u < start + probability {
+            break;
+        }
+        start += probability;
+    }
+
+    picked.map(|(index, probability, start)| {
+        let stretched = ((u - start) / probability).clamp(0., 1. - Float::EPSILON);
+        (index, probability, stretched)
+    })
+}
+
+// Density of `cosine_direction`.
+fn cosine_pdf(normal: &Vec3, direction: &Vec3) -> Float {
+    Float::max(0., normal.dot_product(direction)) / PI
+}
+
+fn cosine_direction(normal: &Vec3, random: (Float, Float)) -> Vec3 {
+    let radius = random.0.sqrt();
+    let angle = 2. * PI * random.1;
+
+    to_world(
+        &Vec3::new(
+            radius * angle.cos(),
+            radius * angle.sin(),
+            (1. - random.0).sqrt(),
+        ),
+        normal,
+    )
+}
+
+// From a frame where `normal` is the z axis.
+fn to_world(local: &Vec3, normal: &Vec3) -> Vec3 {
+    let (tangent, bitangent) = normal.orthonormal_basis();
+
+    tangent * local.x + bitangent * local.y + *normal * local.z
+}
+
+fn average(color: &Vec3) -> Float {
+    (color.x + color.y + color.z) / 3.
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::conductor::Conductor;
+    use crate::geometry::material::dielectric::Dielectric;
+    use crate::geometry::material::dispersion::Dispersion;
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::pbr::Pbr;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::{pick_lobe, to_world, Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::random::Random;
+    use crate::utils::rgb::RGB;
+
+    // Materials without specular directions, whose samples all come from `pdf`. The legacy Phong
+    // model comes last, it isn't energy conserving.
+    fn materials() -> Vec<Box<dyn Material>> {
+        vec![
+            Box::new(Lambertian {
+                color: RGB::new(200, 100, 50),
+                color_texture: None,
+            }),
+            Box::new(Conductor {
+                color: RGB::new(255, 195, 86),
+                roughness: 0.4,
+                color_texture: None,
+                roughness_texture: None,
+            }),
+            Box::new(Pbr {
+                base_color: RGB::new(200, 100, 50),
+                metallic: 0.3,
+                roughness: 0.5,
+                ior: 1.5,
+                transmission: 0.,
+                base_color_texture: None,
+                metallic_roughness_texture: None,
+            }),
+            Box::new(Phong {
+                color: RGB::new(200, 100, 50),
+                albedo: (0.6, 0.3, 0., 0.),
+                specular_exponent: 50.,
+                refractive_index: 1.,
+                color_texture: None,
+                specular_texture: None,
+            }),
+        ]
+    }
+
+    fn surface(material: &dyn Material) -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color: material.color().as_vector() / 255.,
+            uv: None,
+            footprint: 0.,
+            point: Vec3::zero(),
+            local_point: Vec3::zero(),
+            wavelength: None,
+        }
+    }
+
+    #[test]
+    fn frame_is_orthonormal() {
+        for normal in &[
+            Vec3::new(0., 0., 1.),
+            Vec3::new(0., 0., -1.),
+            Vec3::new(1., 2., -3.).normalize(),
+        ] {
+            let x = to_world(&Vec3::new(1., 0., 0.), normal);
+            let y = to_world(&Vec3::new(0., 1., 0.), normal);
+
+            assert_approx_eq!(to_world(&Vec3::new(0., 0., 1.), normal), *normal);
+            assert_approx_eq!(x.length(), 1.);
+            assert_approx_eq!(y.length(), 1.);
+            assert_approx_eq!(x.dot_product(&y), 0.);
+            assert_approx_eq!(x.dot_product(normal), 0.);
+        }
+    }
+
+    #[test]
+    fn lobes_are_picked_by_weight() {
+        assert_eq!(pick_lobe(&[1., 3.], 0.2), Some((0, 0.25, 0.8)));
+        assert_eq!(pick_lobe(&[1., 3.], 0.625), Some((1, 0.75, 0.5)));
+        assert_eq!(pick_lobe(&[0., 2.], 0.), Some((1, 1., 0.)));
+        assert_eq!(pick_lobe(&[0., 0.], 0.5), None);
+    }
+
+    #[test]
+    fn samples_are_weighted_by_pdf() {
+        let to_viewer = Vec3::new(1., 2., 0.).normalize();
+        let mut random = Random::new(7);
+
+        for material in materials() {
+            let surface = surface(material.as_ref());
+
+            for _ in 0..100 {
+                let sample = match material.sample(&surface, &to_viewer, random.pair()) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                let pdf = material.pdf(&surface, &to_viewer, &sample.direction);
+                let value = material.evaluate(&surface, &to_viewer, &sample.direction);
+
+                assert!(pdf > 0.);
+                assert_approx_eq!(sample.weight * pdf, value, 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn energy_is_conserved() {
+        // Averaged over the sphere of incoming light, a surface sends back at most what it got.
+        let to_viewer = Vec3::new(1., 2., 0.).normalize();
+        let mut random = Random::new(11);
+        let count = 20_000;
+
+        for material in materials().iter().take(3) {
+            let surface = surface(material.as_ref());
+            let total = (0..count).fold(Vec3::zero(), |sum, _| {
+                match material.sample(&surface, &to_viewer, random.pair()) {
+                    Some(sample) => sum + sample.weight,
+                    None => sum,
+                }
+            }) / count as Float;
+
+            assert!(total.x <= 1.02 && total.y <= 1.02 && total.z <= 1.02);
+            assert!(total.x > 0.1);
+        }
+    }
+
+    #[test]
+    fn glass_splits_light() {
+        let glass = Dielectric {
+            color: RGB::new(255, 255, 255),
+            ior: 1.5,
+            dispersion: None,
+            color_texture: None,
+        };
+        let surface = surface(&glass);
+        let to_viewer = Vec3::new(0., 1., 0.);
+
+        let lobes = glass.specular(&surface, &to_viewer);
+        assert_eq!(lobes.len(), 2);
+        assert_approx_eq!(lobes[0].weight, Vec3::new(0.04, 0.04, 0.04));
+        assert_approx_eq!(lobes[1].direction, Vec3::new(0., -1., 0.));
+        assert_approx_eq!(lobes[0].weight + lobes[1].weight, Vec3::new(1., 1., 1.));
+
+        // Past the critical angle light leaving the glass is reflected back in.
+        let inside = Vec3::new(0.9, -0.3, 0.).normalize();
+        let lobes = glass.specular(&surface, &inside);
+        assert_eq!(lobes.len(), 1);
+        assert_approx_eq!(lobes[0].weight, Vec3::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn dispersion_bends_blue_more() {
+        let prism = Dielectric {
+            color: RGB::new(255, 255, 255),
+            ior: 1.5,
+            dispersion: Some(Dispersion::from_abbe(1.5, 30.)),
+            color_texture: None,
+        };
+        let to_viewer = Vec3::new(1., 1., 0.).normalize();
+        let refracted = |wavelength| {
+            let surface = Surface {
+                wavelength: Some(wavelength),
+                ..surface(&prism)
+            };
+            prism.specular(&surface, &to_viewer)[1].direction
+        };
+
+        assert!(prism.dispersive());
+        // Closer to the normal, i.e. further from the incoming direction.
+        assert!(refracted(450.).y < refracted(650.).y);
+    }
+}
@@ -0,0 +1,261 @@
+use super::texture::Texture;
+use super::{
+    average, cosine_direction, cosine_pdf, microfacet, pick_lobe, scattered, Material, Sample,
+    Surface,
+};
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Metallic-roughness as in glTF: GGX reflection plus a Lambertian term for the light that
+// enters dielectrics and scatters back out.
+pub struct Pbr {
+    pub base_color: RGB,
+    pub metallic: Float,
+    pub roughness: Float,
+    pub ior: Float,
+    // Share of the non-metallic part that lets light through instead of scattering it.
+    pub transmission: Float,
+    // Multiplies `base_color`.
+    pub base_color_texture: Option<Arc<dyn Texture>>,
+    // Multiplies `roughness` with its green channel and `metallic` with its blue one.
+    pub metallic_roughness_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Pbr {
+    fn metallic(&self, surface: &Surface) -> Float {
+        surface
+            .lookup(&self.metallic_roughness_texture)
+            .map_or(self.metallic, |texel| self.metallic * texel.z)
+    }
+
+    fn roughness(&self, surface: &Surface) -> Float {
+        surface
+            .lookup(&self.metallic_roughness_texture)
+            .map_or(self.roughness, |texel| self.roughness * texel.y)
+    }
+
+    // Reflectance at normal incidence: from the IOR for dielectrics, the base color for metals.
+    fn reflectance(&self, surface: &Surface) -> Vec3 {
+        let dielectric = ((self.ior - 1.) / (self.ior + 1.)).powi(2);
+
+        Vec3::new(dielectric, dielectric, dielectric).lerp(&surface.color, self.metallic(surface))
+    }
+
+    fn diffuse_weight(&self, surface: &Surface) -> Float {
+        (1. - self.metallic(surface)) * (1. - self.transmission)
+    }
+
+    fn transmittance(&self, surface: &Surface, to_viewer: &Vec3) -> Vec3 {
+        let cos_view = surface.facing(to_viewer).dot_product(to_viewer);
+        let passed = Vec3::new(1., 1., 1.)
+            - microfacet::schlick(&self.reflectance(surface), cos_view);
+
+        passed * surface.color * (self.transmission * (1. - self.metallic(surface)))
+    }
+
+    fn refracted(&self, surface: &Surface, to_viewer: &Vec3) -> Vec3 {
+        (-*to_viewer)
+            .refract(&surface.normal, self.ior, 1.)
+            .normalize()
+    }
+
+    // Lobe weights for `sample`: specular, diffuse and transmitted light.
+    fn lobes(&self, surface: &Surface, to_viewer: &Vec3) -> [Float; 3] {
+        let cos_view = surface.facing(to_viewer).dot_product(to_viewer);
+        let specular = average(&microfacet::schlick(
+            &self.reflectance(surface),
+            cos_view,
+        ));
+        let entering = (1. - specular) * average(&surface.color);
+
+        [
+            specular,
+            entering * self.diffuse_weight(surface),
+            entering * self.transmission * (1. - self.metallic(surface)),
+        ]
+    }
+}
+
+impl Material for Pbr {
+    fn color(&self) -> RGB {
+        self.base_color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.base_color_texture)? * self.base_color.as_vector() / 255.)
+    }
+
+    fn ior(&self) -> Float {
+        self.ior
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        let normal = surface.facing(to_viewer);
+        let cos_light = normal.dot_product(to_light);
+        if cos_light <= 0. {
+            return Vec3::zero();
+        }
+
+        let reflectance = self.reflectance(surface);
+        let specular = microfacet::evaluate(
+            &reflectance,
+            microfacet::alpha(self.roughness(surface)),
+            &normal,
+            to_viewer,
+            to_light,
+        );
+
+        let half = (*to_viewer + *to_light).normalize();
+        let fresnel = microfacet::schlick(&reflectance, half.dot_product(to_viewer));
+        let diffuse = (Vec3::new(1., 1., 1.) - fresnel)
+            * surface.color
+            * (self.diffuse_weight(surface) * cos_light / PI);
+
+        specular + diffuse
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let (lobe, probability, u) = pick_lobe(&self.lobes(surface, to_viewer), random.0)?;
+        let normal = surface.facing(to_viewer);
+
+        let direction = match lobe {
+            0 => microfacet::sample(
+                microfacet::alpha(self.roughness(surface)),
+                &normal,
+                to_viewer,
+                (u, random.1),
+            ),
+            1 => cosine_direction(&normal, (u, random.1)),
+            _ => {
+                return Some(Sample {
+                    direction: self.refracted(surface, to_viewer),
+                    weight: self.transmittance(surface, to_viewer) / probability,
+                })
+            }
+        };
+
+        scattered(self, surface, to_viewer, direction)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        let lobes = self.lobes(surface, to_viewer);
+        let total: Float = lobes.iter().sum();
+        if total <= 0. {
+            return 0.;
+        }
+
+        let normal = surface.facing(to_viewer);
+        let specular = microfacet::pdf(
+            microfacet::alpha(self.roughness(surface)),
+            &normal,
+            to_viewer,
+            to_light,
+        );
+
+        (lobes[0] * specular + lobes[1] * cosine_pdf(&normal, to_light)) / total
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let mut lobes = Vec::new();
+
+        let mirror = microfacet::mirror(
+            &self.reflectance(surface),
+            self.roughness(surface),
+            surface,
+            to_viewer,
+        );
+        if mirror != Vec3::zero() {
+            lobes.push(Sample {
+                direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+                weight: mirror,
+            });
+        }
+
+        let transmittance = self.transmittance(surface, to_viewer);
+        if transmittance != Vec3::zero() {
+            lobes.push(Sample {
+                direction: self.refracted(surface, to_viewer),
+                weight: transmittance,
+            });
+        }
+
+        lobes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::consts::PI;
+    use crate::geometry::material::pbr::Pbr;
+    use crate::geometry::material::{Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::rgb::RGB;
+
+    fn pbr(metallic: Float, roughness: Float) -> Pbr {
+        Pbr {
+            base_color: RGB::new(255, 128, 0),
+            metallic,
+            roughness,
+            ior: 1.5,
+            transmission: 0.,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+        }
+    }
+
+    fn surface(color: Vec3) -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color,
+            uv: None,
+            footprint: 0.,
+            point: Vec3::zero(),
+            local_point: Vec3::zero(),
+            wavelength: None,
+        }
+    }
+
+    #[test]
+    fn metals_reflect_their_color() {
+        let surface = surface(Vec3::new(1., 0.5, 0.));
+        let light = Vec3::new(1., 1., 0.).normalize();
+        let viewer = Vec3::new(-1., 1., 0.).normalize();
+
+        let metal = pbr(1., 0.3).evaluate(&surface, &viewer, &light);
+        assert!(metal.x > metal.y && metal.y > metal.z);
+
+        let mirror = pbr(1., 0.).specular(&surface, &Vec3::new(0., 1., 0.));
+        assert_eq!(mirror.len(), 1);
+        assert_approx_eq!(mirror[0].weight, surface.color);
+
+        // Nothing comes from below the surface.
+        let below = Vec3::new(1., -1., 0.).normalize();
+        assert_eq!(
+            pbr(1., 0.3).evaluate(&surface, &viewer, &below),
+            Vec3::zero()
+        );
+    }
+
+    #[test]
+    fn rough_dielectrics_are_mostly_diffuse() {
+        let surface = surface(Vec3::new(1., 1., 1.));
+        let normal = surface.normal;
+
+        // Head-on a white matte surface sends back close to 1/pi of the light.
+        let matte = pbr(0., 1.).evaluate(&surface, &normal, &normal);
+        assert_approx_eq!(matte.x * PI, 1., 0.1);
+        assert!(pbr(0., 1.).specular(&surface, &normal).is_empty());
+    }
+}
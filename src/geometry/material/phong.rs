@@ -0,0 +1,119 @@
+use super::texture::Texture;
+use super::{
+    average, cosine_direction, cosine_pdf, pick_lobe, scattered, Material, Sample, Surface,
+};
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// The original shading model: weights of diffuse, specular, reflected and refracted light.
+// Kept as it was for existing scenes, so it doesn't conserve energy.
+pub struct Phong {
+    pub color: RGB,
+    pub albedo: (Float, Float, Float, Float),
+    pub specular_exponent: Float,
+    pub refractive_index: Float,
+    // Multiplies `color`.
+    pub color_texture: Option<Arc<dyn Texture>>,
+    // Gray map scaling the specular highlight, e.g. to keep it off rusty parts.
+    pub specular_texture: Option<Arc<dyn Texture>>,
+}
+
+impl Phong {
+    // Lobe weights for `sample`: diffuse, then the reflected and the refracted ray. Highlights
+    // are too narrow for cosine sampling, they come from lighting the surface directly.
+    fn lobes(&self, surface: &Surface) -> [Float; 3] {
+        [
+            self.albedo.0 * average(&surface.color),
+            self.albedo.2,
+            self.albedo.3,
+        ]
+    }
+
+    fn reflected(&self, surface: &Surface, to_viewer: &Vec3) -> Sample {
+        Sample {
+            direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+            weight: Vec3::new(1., 1., 1.) * self.albedo.2,
+        }
+    }
+
+    fn refracted(&self, surface: &Surface, to_viewer: &Vec3) -> Sample {
+        Sample {
+            direction: (-*to_viewer)
+                .refract(&surface.normal, self.refractive_index, 1.)
+                .normalize(),
+            weight: Vec3::new(1., 1., 1.) * self.albedo.3,
+        }
+    }
+}
+
+impl Material for Phong {
+    fn color(&self) -> RGB {
+        self.color
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        Some(surface.lookup(&self.color_texture)? * self.color.as_vector() / 255.)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        let normal = &surface.normal;
+        let diffuse = Float::max(0., to_light.dot_product(normal));
+        let specular = Float::max(0., to_light.reflect(normal).dot_product(&-*to_viewer))
+            .powf(self.specular_exponent)
+            * surface.lookup(&self.specular_texture).map_or(1., |gray| average(&gray));
+
+        (surface.color * (diffuse * self.albedo.0)
+            + Vec3::new(1., 1., 1.) * (specular * self.albedo.1))
+            / PI
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let (lobe, probability, u) = pick_lobe(&self.lobes(surface), random.0)?;
+
+        if lobe == 0 {
+            let direction = cosine_direction(&surface.facing(to_viewer), (u, random.1));
+            return scattered(self, surface, to_viewer, direction);
+        }
+
+        let mut sample = if lobe == 1 {
+            self.reflected(surface, to_viewer)
+        } else {
+            self.refracted(surface, to_viewer)
+        };
+        sample.weight = sample.weight / probability;
+
+        Some(sample)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        let lobes = self.lobes(surface);
+        let total: Float = lobes.iter().sum();
+        if total <= 0. {
+            return 0.;
+        }
+        let probability = lobes[0] / total;
+
+        probability * cosine_pdf(&surface.facing(to_viewer), to_light)
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let mut lobes = Vec::new();
+        if self.albedo.2 != 0. {
+            lobes.push(self.reflected(surface, to_viewer));
+        }
+        if self.albedo.3 != 0. {
+            lobes.push(self.refracted(surface, to_viewer));
+        }
+
+        lobes
+    }
+}
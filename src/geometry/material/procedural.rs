@@ -0,0 +1,163 @@
+use super::texture::Texture;
+use super::Surface;
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::noise;
+use crate::utils::rgb::RGB;
+
+const OCTAVES: usize = 6;
+
+// Which coordinates a procedural texture is computed from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Space {
+    // Objects look cut out of one block of the pattern, moving objects slide through it.
+    World,
+    // Every object carries its own copy of the pattern along.
+    Object,
+}
+
+// Shapes of procedural textures, each a value in 0..1 at every point of space. A pattern unit
+// is one world unit before the texture's scale is applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    // Cubes one unit wide, alternating between 0 and 1.
+    Checker,
+    // Ramp along the direction, 0.5 at the origin and one unit long.
+    Gradient(Vec3),
+    // Perlin noise, blobs about one unit wide.
+    Noise,
+    // Several octaves of noise, billowy like smoke.
+    Turbulence,
+    // Veins across x, two units apart, bent by turbulence of the given strength.
+    Marble(Float),
+    // Rings around the y axis one unit apart, made irregular by noise of the given strength.
+    Wood(Float),
+    // Distance to random points scattered about one per unit cube, cells like stone or scales.
+    Voronoi,
+}
+
+impl Pattern {
+    pub fn value(&self, point: &Vec3) -> Float {
+        let value = match *self {
+            Pattern::Checker => {
+                let parity = point.x.floor() + point.y.floor() + point.z.floor();
+                parity.rem_euclid(2.)
+            }
+            Pattern::Gradient(direction) => 0.5 + point.dot_product(&direction),
+            Pattern::Noise => 0.5 + 0.5 * noise::perlin(point),
+            Pattern::Turbulence => noise::turbulence(point, OCTAVES),
+            Pattern::Marble(strength) => {
+                let phase = point.x + strength * noise::turbulence(point, OCTAVES);
+                0.5 + 0.5 * (PI * phase).sin()
+            }
+            Pattern::Wood(strength) => {
+                let radius = (point.x * point.x + point.z * point.z).sqrt();
+                (radius + strength * noise::perlin(point)).rem_euclid(1.)
+            }
+            Pattern::Voronoi => noise::voronoi(point),
+        };
+
+        value.clamp(0., 1.)
+    }
+}
+
+// Blends two colors by a pattern, so objects can be textured without image files.
+pub struct Procedural {
+    pub pattern: Pattern,
+    // At pattern values 0 and 1.
+    pub colors: (RGB, RGB),
+    // Pattern units per world unit, larger values make the pattern finer.
+    pub scale: Float,
+    pub space: Space,
+}
+
+impl Texture for Procedural {
+    fn sample(&self, surface: &Surface) -> Option<Vec3> {
+        let point = match self.space {
+            Space::World => surface.point,
+            Space::Object => surface.local_point,
+        };
+        let value = self.pattern.value(&(point * self.scale));
+        let (low, high) = (self.colors.0.as_vector(), self.colors.1.as_vector());
+
+        Some(low.lerp(&high, value) / 255.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::procedural::{Pattern, Procedural, Space};
+    use crate::geometry::material::texture::Texture;
+    use crate::geometry::material::Surface;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::rgb::RGB;
+
+    fn surface(point: Vec3, local_point: Vec3) -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color: Vec3::new(1., 1., 1.),
+            uv: None,
+            footprint: 0.,
+            point,
+            local_point,
+            wavelength: None,
+        }
+    }
+
+    #[test]
+    fn patterns_vary() {
+        let patterns = [
+            Pattern::Checker,
+            Pattern::Gradient(Vec3::new(0., 0.5, 0.)),
+            Pattern::Noise,
+            Pattern::Turbulence,
+            Pattern::Marble(5.),
+            Pattern::Wood(0.3),
+            Pattern::Voronoi,
+        ];
+
+        for pattern in &patterns {
+            let values: Vec<Float> = (0..200)
+                .map(|i| {
+                    let i = i as Float;
+                    pattern.value(&(Vec3::new(i, (i * 7.) % 13., -i) * 0.37))
+                })
+                .collect();
+            let lowest = values.iter().cloned().fold(1., Float::min);
+            let highest = values.iter().cloned().fold(0., Float::max);
+
+            assert!(highest - lowest > 0.2, "{:?} is flat", pattern);
+        }
+    }
+
+    #[test]
+    fn checker_alternates() {
+        assert_eq!(Pattern::Checker.value(&Vec3::new(0.5, 0.5, 0.5)), 0.);
+        assert_eq!(Pattern::Checker.value(&Vec3::new(1.5, 0.5, 0.5)), 1.);
+        assert_eq!(Pattern::Checker.value(&Vec3::new(-0.5, 0.5, 0.5)), 1.);
+        assert_eq!(Pattern::Checker.value(&Vec3::new(-0.5, -0.5, 0.5)), 0.);
+    }
+
+    #[test]
+    fn spaces() {
+        let texture = Procedural {
+            pattern: Pattern::Gradient(Vec3::new(1., 0., 0.)),
+            colors: (RGB::new(0, 0, 0), RGB::new(255, 0, 255)),
+            scale: 0.5,
+            space: Space::Object,
+        };
+        let surface = surface(Vec3::new(-10., 0., 0.), Vec3::new(0.5, 0., 0.));
+
+        assert_approx_eq!(texture.sample(&surface).unwrap(), Vec3::new(0.75, 0., 0.75));
+
+        let world = Procedural {
+            space: Space::World,
+            ..texture
+        };
+        assert_approx_eq!(world.sample(&surface).unwrap(), Vec3::zero());
+    }
+}
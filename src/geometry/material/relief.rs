@@ -0,0 +1,263 @@
+use super::subsurface::Medium;
+use super::texture::Texture;
+use super::{average, Material, Sample, Surface};
+use crate::geometry::object::Uv;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::sync::Arc;
+
+// Bump maps are differentiated over the width of the ray, but never over less than this.
+const MIN_BUMP_STEP: Float = 1e-4;
+
+// Detail drawn by tilting the shading normal instead of with extra geometry.
+pub enum Relief {
+    // Tangent-space normals stored as colors, as in glTF: red along u, green up the texture,
+    // i.e. against v, and blue out of the surface. `scale` exaggerates or flattens the tilt.
+    NormalMap {
+        texture: Arc<dyn Texture>,
+        scale: Float,
+    },
+    // Heights from the brightness of the texture, full brightness rising `height` world units.
+    BumpMap {
+        texture: Arc<dyn Texture>,
+        height: Float,
+    },
+}
+
+impl Relief {
+    fn normal(&self, surface: &Surface) -> Option<Vec3> {
+        match self {
+            Relief::NormalMap { texture, scale } => {
+                let texel = texture.sample(surface)? * 2. - Vec3::new(1., 1., 1.);
+
+                Some(
+                    (surface.tangent * (texel.x * scale) - surface.bitangent * (texel.y * scale)
+                        + surface.normal * texel.z)
+                        .normalize(),
+                )
+            }
+            Relief::BumpMap { texture, height } => {
+                // Differences as wide as the ray smooth out bumps smaller than a pixel.
+                let step = surface.footprint.max(MIN_BUMP_STEP);
+                let height_at =
+                    |surface: &Surface| Some(average(&texture.sample(surface)?) * height);
+
+                let base = height_at(surface)?;
+                let slope_u = (height_at(&shifted(surface, false, step))? - base) / step;
+                let slope_v = (height_at(&shifted(surface, true, step))? - base) / step;
+
+                Some(
+                    (surface.normal - surface.tangent * slope_u - surface.bitangent * slope_v)
+                        .normalize(),
+                )
+            }
+        }
+    }
+}
+
+// The surface `distance` further along its tangent, or its bitangent with `along_v`. Only
+// exact in object space for objects that are moved but not rotated.
+fn shifted(surface: &Surface, along_v: bool, distance: Float) -> Surface {
+    let direction = if along_v {
+        surface.bitangent
+    } else {
+        surface.tangent
+    };
+    let uv = surface.uv.map(|uv| {
+        let shift = distance * uv.density;
+        if along_v {
+            Uv {
+                v: uv.v + shift,
+                ..uv
+            }
+        } else {
+            Uv {
+                u: uv.u + shift,
+                ..uv
+            }
+        }
+    });
+
+    Surface {
+        point: surface.point + direction * distance,
+        local_point: surface.local_point + direction * distance,
+        uv,
+        ..*surface
+    }
+}
+
+// Any material with relief on top of it.
+pub struct Bumped {
+    pub material: Box<dyn Material>,
+    pub relief: Relief,
+}
+
+impl Material for Bumped {
+    fn color(&self) -> RGB {
+        self.material.color()
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.texture_color(surface)
+    }
+
+    // Tilts whatever normal the material underneath shades with. Normals tilted past the
+    // surface are dropped, they would shade it black.
+    fn shading_normal(&self, surface: &Surface) -> Option<Vec3> {
+        let surface = Surface {
+            normal: self
+                .material
+                .shading_normal(surface)
+                .unwrap_or(surface.normal),
+            ..*surface
+        };
+
+        self.relief
+            .normal(&surface)
+            .filter(|normal| normal.dot_product(&surface.normal) > 0.)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.material.emission()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn ior(&self) -> Float {
+        self.material.ior()
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        self.material.subsurface()
+    }
+
+    fn cut_out(&self, surface: &Surface) -> bool {
+        self.material.cut_out(surface)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        self.material.evaluate(surface, to_viewer, to_light)
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        self.material.sample(surface, to_viewer, random)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        self.material.pdf(surface, to_viewer, to_light)
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        self.material.specular(surface, to_viewer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::procedural::{Pattern, Procedural, Space};
+    use crate::geometry::material::relief::{Bumped, Relief};
+    use crate::geometry::material::texture::Texture;
+    use crate::geometry::material::{Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::rgb::RGB;
+
+    use std::sync::Arc;
+
+    struct Uniform(Vec3);
+
+    impl Texture for Uniform {
+        fn sample(&self, _surface: &Surface) -> Option<Vec3> {
+            Some(self.0)
+        }
+    }
+
+    fn bumped(relief: Relief) -> Bumped {
+        Bumped {
+            material: Box::new(Lambertian {
+                color: RGB::new(255, 255, 255),
+                color_texture: None,
+            }),
+            relief,
+        }
+    }
+
+    fn surface() -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color: Vec3::new(1., 1., 1.),
+            uv: None,
+            footprint: 0.,
+            point: Vec3::new(0.25, 0., 0.),
+            local_point: Vec3::zero(),
+            wavelength: None,
+        }
+    }
+
+    #[test]
+    fn normal_maps() {
+        let normal = |texel: Vec3| {
+            bumped(Relief::NormalMap {
+                texture: Arc::new(Uniform(texel)),
+                scale: 1.,
+            })
+            .shading_normal(&surface())
+        };
+
+        // Flat, leaning towards u, leaning up the texture and pointing into the surface.
+        assert_approx_eq!(
+            normal(Vec3::new(0.5, 0.5, 1.)).unwrap(),
+            Vec3::new(0., 1., 0.)
+        );
+        assert_approx_eq!(
+            normal(Vec3::new(1., 0.5, 1.)).unwrap(),
+            Vec3::new(1., 1., 0.).normalize()
+        );
+        assert_approx_eq!(
+            normal(Vec3::new(0.5, 1., 1.)).unwrap(),
+            Vec3::new(0., 1., 1.).normalize()
+        );
+        assert!(normal(Vec3::new(0.5, 0.5, 0.)).is_none());
+    }
+
+    #[test]
+    fn bump_maps() {
+        // Rising one unit per unit along x.
+        let slope = Procedural {
+            pattern: Pattern::Gradient(Vec3::new(1., 0., 0.)),
+            colors: (RGB::new(0, 0, 0), RGB::new(255, 255, 255)),
+            scale: 1.,
+            space: Space::World,
+        };
+        let material = bumped(Relief::BumpMap {
+            texture: Arc::new(slope),
+            height: 1.,
+        });
+
+        assert_approx_eq!(
+            material.shading_normal(&surface()).unwrap(),
+            Vec3::new(-1., 1., 0.).normalize(),
+            1e-3
+        );
+
+        let flat = bumped(Relief::BumpMap {
+            texture: Arc::new(Uniform(Vec3::new(0.3, 0.3, 0.3))),
+            height: 1.,
+        });
+        assert_approx_eq!(
+            flat.shading_normal(&surface()).unwrap(),
+            Vec3::new(0., 1., 0.)
+        );
+    }
+}
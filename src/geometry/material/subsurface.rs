@@ -0,0 +1,167 @@
+use super::{microfacet, scattered, Material, Sample, Surface};
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+// Stuff light travels through below the surface of a material, scattering and being absorbed
+// along the way. Coefficients are per world unit and per color channel, their inverses are
+// how far light gets on average before it's scattered or absorbed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    pub scattering: Vec3,
+    pub absorption: Vec3,
+}
+
+impl Medium {
+    pub fn extinction(&self) -> Vec3 {
+        self.scattering + self.absorption
+    }
+
+    // Share of light left after `distance`.
+    pub fn transmittance(&self, distance: Float) -> Vec3 {
+        let extinction = self.extinction();
+
+        Vec3::new(
+            (-extinction.x * distance).exp(),
+            (-extinction.y * distance).exp(),
+            (-extinction.z * distance).exp(),
+        )
+    }
+
+    // Distance light of one color channel travels before it's scattered, picked with a uniform
+    // number in 0..1 in proportion to the transmittance.
+    pub fn free_path(&self, channel: usize, u: Float) -> Float {
+        -(1. - u).ln() / self.extinction()[channel].max(Float::EPSILON)
+    }
+
+    // Scatters light evenly in all directions.
+    pub fn direction(&self, random: (Float, Float)) -> Vec3 {
+        let z = 1. - 2. * random.0;
+        let radius = (1. - z * z).max(0.).sqrt();
+        let angle = 2. * PI * random.1;
+
+        Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+    }
+}
+
+// Wax, marble or skin: a smooth or glossy surface over a medium light scatters in, so it
+// bleeds softly past shadow edges and into thin parts. The surface itself only reflects, the
+// rest of the light is carried through the medium by the integrator.
+pub struct Translucent {
+    pub medium: Medium,
+    pub ior: Float,
+    pub roughness: Float,
+}
+
+impl Translucent {
+    fn reflectance(&self) -> Vec3 {
+        let reflectance = ((self.ior - 1.) / (self.ior + 1.)).powi(2);
+
+        Vec3::new(reflectance, reflectance, reflectance)
+    }
+}
+
+impl Material for Translucent {
+    // Light surviving each scattering, roughly the color the material ends up with.
+    fn color(&self) -> RGB {
+        let extinction = self.medium.extinction().max(&Vec3::new(
+            Float::EPSILON,
+            Float::EPSILON,
+            Float::EPSILON,
+        ));
+        let albedo = self.medium.scattering / extinction;
+
+        RGB::from_vector(&(albedo * 255.))
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        Some(self.medium)
+    }
+
+    fn ior(&self) -> Float {
+        self.ior
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        microfacet::evaluate(
+            &self.reflectance(),
+            microfacet::alpha(self.roughness),
+            &surface.facing(to_viewer),
+            to_viewer,
+            to_light,
+        )
+    }
+
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let direction = microfacet::sample(
+            microfacet::alpha(self.roughness),
+            &surface.facing(to_viewer),
+            to_viewer,
+            random,
+        );
+
+        scattered(self, surface, to_viewer, direction)
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        microfacet::pdf(
+            microfacet::alpha(self.roughness),
+            &surface.facing(to_viewer),
+            to_viewer,
+            to_light,
+        )
+    }
+
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let weight = microfacet::mirror(&self.reflectance(), self.roughness, surface, to_viewer);
+        if weight == Vec3::zero() {
+            return Vec::new();
+        }
+
+        vec![Sample {
+            direction: (-*to_viewer).reflect(&surface.normal).normalize(),
+            weight,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::subsurface::Medium;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::random::Random;
+
+    #[test]
+    fn free_paths_follow_transmittance() {
+        let medium = Medium {
+            scattering: Vec3::new(2., 1., 0.5),
+            absorption: Vec3::new(0., 1., 0.5),
+        };
+        let mut random = Random::new(13);
+        let count = 20_000;
+
+        // The mean free path is the inverse of the extinction.
+        let mean = (0..count)
+            .map(|_| medium.free_path(1, random.uniform()))
+            .sum::<Float>()
+            / count as Float;
+        assert_approx_eq!(mean, 0.5, 0.02);
+
+        let beyond = (0..count)
+            .filter(|_| medium.free_path(2, random.uniform()) > 1.)
+            .count() as Float
+            / count as Float;
+        assert_approx_eq!(beyond, medium.transmittance(1.).z, 0.02);
+
+        for _ in 0..100 {
+            assert_approx_eq!(medium.direction(random.pair()).length(), 1.);
+        }
+    }
+}
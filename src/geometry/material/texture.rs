@@ -0,0 +1,278 @@
+use super::Surface;
+use crate::geometry::object::Uv;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+use image::RgbImage;
+
+// Varies a material parameter over a surface, with values in 0..1. Colors are display values
+// like `Surface::color`.
+pub trait Texture: Send + Sync {
+    // None where the texture isn't defined, e.g. an image on a surface without coordinates.
+    fn sample(&self, surface: &Surface) -> Option<Vec3>;
+}
+
+// What happens to texture coordinates outside of 0..1, as in glTF.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+struct Level {
+    width: usize,
+    height: usize,
+    texels: Vec<Vec3>,
+}
+
+// Image read with bilinear filtering inside a mip level and blended between the two levels
+// closest to the ray footprint.
+pub struct ImageTexture {
+    // The image itself first, each next level half the size down to a single texel.
+    levels: Vec<Level>,
+    // Along u, then along v.
+    wrap: (Wrap, Wrap),
+}
+
+impl ImageTexture {
+    // Rows go from top to bottom, so v = 0 is the top edge of the image.
+    pub fn new(width: usize, height: usize, texels: Vec<Vec3>, wrap: (Wrap, Wrap)) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "Texture must have at least one texel"
+        );
+        assert_eq!(
+            texels.len(),
+            width * height,
+            "Texel count doesn't match texture size"
+        );
+
+        let mut levels = vec![Level {
+            width,
+            height,
+            texels,
+        }];
+        while let Some(level) = levels.last().and_then(Level::downsampled) {
+            levels.push(level);
+        }
+
+        Self { levels, wrap }
+    }
+
+    pub fn load(path: &str, wrap: (Wrap, Wrap)) -> Result<Self, String> {
+        let image = image::open(path).map_err(|e| format!("Can't load texture {}: {}", path, e))?;
+
+        Ok(Self::from_image(&image.to_rgb(), wrap))
+    }
+
+    pub fn from_image(image: &RgbImage, wrap: (Wrap, Wrap)) -> Self {
+        let texels = image
+            .pixels()
+            .map(|pixel| Vec3::new(pixel[0] as Float, pixel[1] as Float, pixel[2] as Float) / 255.)
+            .collect();
+
+        Self::new(
+            image.width() as usize,
+            image.height() as usize,
+            texels,
+            wrap,
+        )
+    }
+
+    // `footprint` is the width of the ray where it meets the surface, in world units.
+    pub fn sample_uv(&self, uv: &Uv, footprint: Float) -> Vec3 {
+        let image = &self.levels[0];
+        let texels = footprint * uv.density * image.width.max(image.height) as Float;
+        let level = texels.max(1.).log2().min((self.levels.len() - 1) as Float);
+
+        let fine = level.floor() as usize;
+        let color = self.levels[fine].bilinear(uv.u, uv.v, self.wrap);
+
+        match self.levels.get(fine + 1) {
+            Some(coarse) if level > fine as Float => color.lerp(
+                &coarse.bilinear(uv.u, uv.v, self.wrap),
+                level - fine as Float,
+            ),
+            _ => color,
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, surface: &Surface) -> Option<Vec3> {
+        Some(self.sample_uv(surface.uv.as_ref()?, surface.footprint))
+    }
+}
+
+impl Level {
+    fn texel(&self, x: i64, y: i64, wrap: (Wrap, Wrap)) -> Vec3 {
+        let x = wrap_index(x, self.width, wrap.0);
+        let y = wrap_index(y, self.height, wrap.1);
+
+        self.texels[y * self.width + x]
+    }
+
+    // Texel centers sit at half-integer coordinates.
+    fn bilinear(&self, u: Float, v: Float, wrap: (Wrap, Wrap)) -> Vec3 {
+        let x = u * self.width as Float - 0.5;
+        let y = v * self.height as Float - 0.5;
+        let (left, top) = (x.floor(), y.floor());
+        let (dx, dy) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+
+        let upper = self
+            .texel(left, top, wrap)
+            .lerp(&self.texel(left + 1, top, wrap), dx);
+        let lower = self
+            .texel(left, top + 1, wrap)
+            .lerp(&self.texel(left + 1, top + 1, wrap), dx);
+
+        upper.lerp(&lower, dy)
+    }
+
+    // Averages blocks of 2x2 texels, odd sizes reuse the last row or column. None past 1x1.
+    fn downsampled(&self) -> Option<Self> {
+        if self.width == 1 && self.height == 1 {
+            return None;
+        }
+
+        let (width, height) = (self.width.div_ceil(2), self.height.div_ceil(2));
+        let clamp = (Wrap::ClampToEdge, Wrap::ClampToEdge);
+        let mut texels = Vec::with_capacity(width * height);
+
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let sum = self.texel(2 * x, 2 * y, clamp)
+                    + self.texel(2 * x + 1, 2 * y, clamp)
+                    + self.texel(2 * x, 2 * y + 1, clamp)
+                    + self.texel(2 * x + 1, 2 * y + 1, clamp);
+                texels.push(sum / 4.);
+            }
+        }
+
+        Some(Self {
+            width,
+            height,
+            texels,
+        })
+    }
+}
+
+fn wrap_index(index: i64, size: usize, wrap: Wrap) -> usize {
+    let size = size as i64;
+
+    let wrapped = match wrap {
+        Wrap::Repeat => index.rem_euclid(size),
+        Wrap::MirroredRepeat => {
+            let index = index.rem_euclid(2 * size);
+            if index < size {
+                index
+            } else {
+                2 * size - 1 - index
+            }
+        }
+        Wrap::ClampToEdge => index.clamp(0, size - 1),
+    };
+
+    wrapped as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::texture::{wrap_index, ImageTexture, Wrap};
+    use crate::geometry::object::Uv;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+
+    const REPEAT: (Wrap, Wrap) = (Wrap::Repeat, Wrap::Repeat);
+
+    fn uv(u: f64, v: f64) -> Uv {
+        Uv {
+            u: u as _,
+            v: v as _,
+            density: 1.,
+        }
+    }
+
+    // Black and white checkerboard, 4x4 texels.
+    fn checkerboard(wrap: (Wrap, Wrap)) -> ImageTexture {
+        let texels = (0..16)
+            .map(|i| {
+                let white = (i % 4 + i / 4) % 2 == 0;
+                if white {
+                    Vec3::new(1., 1., 1.)
+                } else {
+                    Vec3::zero()
+                }
+            })
+            .collect();
+
+        ImageTexture::new(4, 4, texels, wrap)
+    }
+
+    #[test]
+    fn wrap_modes() {
+        assert_eq!(wrap_index(5, 4, Wrap::Repeat), 1);
+        assert_eq!(wrap_index(-1, 4, Wrap::Repeat), 3);
+        assert_eq!(wrap_index(4, 4, Wrap::MirroredRepeat), 3);
+        assert_eq!(wrap_index(-1, 4, Wrap::MirroredRepeat), 0);
+        assert_eq!(wrap_index(9, 4, Wrap::MirroredRepeat), 1);
+        assert_eq!(wrap_index(-3, 4, Wrap::ClampToEdge), 0);
+        assert_eq!(wrap_index(7, 4, Wrap::ClampToEdge), 3);
+    }
+
+    #[test]
+    fn bilinear_filtering() {
+        let texture = checkerboard(REPEAT);
+        let white = Vec3::new(1., 1., 1.);
+        let gray = Vec3::new(0.5, 0.5, 0.5);
+
+        // Texel centers, the corner between four texels and one past the right edge.
+        assert_approx_eq!(texture.sample_uv(&uv(0.125, 0.125), 0.), white);
+        assert_approx_eq!(texture.sample_uv(&uv(0.375, 0.125), 0.), Vec3::zero());
+        assert_approx_eq!(texture.sample_uv(&uv(0.25, 0.25), 0.), gray);
+        assert_approx_eq!(texture.sample_uv(&uv(0.25, 0.125), 0.), gray);
+        assert_approx_eq!(texture.sample_uv(&uv(1.125, -0.875), 0.), white);
+
+        // Between the last and the first column only repeating blends the two.
+        let clamped = checkerboard((Wrap::ClampToEdge, Wrap::ClampToEdge));
+        assert_approx_eq!(texture.sample_uv(&uv(1., 0.125), 0.), gray);
+        assert_approx_eq!(clamped.sample_uv(&uv(1., 0.125), 0.), Vec3::zero());
+    }
+
+    #[test]
+    fn mip_mapping() {
+        let texture = checkerboard(REPEAT);
+        let gray = Vec3::new(0.5, 0.5, 0.5);
+
+        assert_eq!(texture.levels.len(), 3);
+        assert_eq!(texture.levels[2].texels, vec![gray]);
+
+        // A ray as wide as a texel reads the full resolution, wider ones average the checkers
+        // out instead of aliasing.
+        assert_approx_eq!(
+            texture.sample_uv(&uv(0.125, 0.125), 0.25),
+            Vec3::new(1., 1., 1.)
+        );
+        assert_approx_eq!(texture.sample_uv(&uv(0.125, 0.125), 0.5), gray);
+        assert_approx_eq!(texture.sample_uv(&uv(0.125, 0.125), 100.), gray);
+
+        // Odd sizes shrink down to one texel too.
+        let odd = ImageTexture::new(3, 1, vec![Vec3::zero(); 3], REPEAT);
+        let sizes: Vec<(usize, usize)> = odd
+            .levels
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(sizes, vec![(3, 1), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn missing_file() {
+        let error = ImageTexture::load("/nonexistent/texture.png", REPEAT)
+            .err()
+            .unwrap();
+
+        assert!(error.starts_with("Can't load texture /nonexistent/texture.png"));
+    }
+}
@@ -0,0 +1,243 @@
+use super::subsurface::Medium;
+use super::{average, pick_lobe, Material, Sample, Surface};
+use crate::geometry::consts::PI;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+use crate::utils::spectrum;
+
+// A transparent coating a few hundred nanometers thick, e.g. soap or a lens coating. Light
+// reflected off its top interferes with light reflected off the material underneath, which
+// brightens some wavelengths and cancels others depending on the thickness and the angle. Only
+// mirror-like reflections are affected, materials without any show no coating.
+pub struct ThinFilm {
+    pub material: Box<dyn Material>,
+    // In nanometers.
+    pub thickness: Float,
+    pub ior: Float,
+}
+
+impl ThinFilm {
+    // Reflectance with the coating, per color channel, and without it.
+    fn reflectance(&self, surface: &Surface, to_viewer: &Vec3) -> (Vec3, Float) {
+        let cos = surface.normal.dot_product(to_viewer).abs().min(1.);
+        let substrate = self.material.ior();
+        let bare = fresnel(1., substrate, cos);
+
+        let coated = match surface.wavelength {
+            Some(wavelength) => {
+                let reflectance = self.coated(substrate, cos, wavelength);
+                Vec3::new(reflectance, reflectance, reflectance)
+            }
+            None => (0..spectrum::BANDS).fold(Vec3::zero(), |sum, band| {
+                let wavelength = spectrum::wavelength(band, 0.5);
+                sum + spectrum::color(band) * self.coated(substrate, cos, wavelength)
+            }),
+        };
+
+        (coated, bare)
+    }
+
+    // Airy's formula for air, the film and the substrate, averaged over both polarizations.
+    fn coated(&self, substrate: Float, cos: Float, wavelength: Float) -> Float {
+        let sin2 = 1. - cos * cos;
+        let cos_film = (1. - sin2 / (self.ior * self.ior)).max(0.).sqrt();
+        let cos_substrate = (1. - sin2 / (substrate * substrate)).max(0.).sqrt();
+        let phase = (4. * PI * self.ior * self.thickness * cos_film / wavelength).cos();
+
+        let airy = |top: Float, bottom: Float| {
+            let interference = 2. * top * bottom * phase;
+            (top * top + bottom * bottom + interference)
+                / (1. + top * top * bottom * bottom + interference)
+        };
+        let perpendicular = airy(
+            amplitude(1., cos, self.ior, cos_film),
+            amplitude(self.ior, cos_film, substrate, cos_substrate),
+        );
+        let parallel = airy(
+            amplitude(1., cos_film, self.ior, cos),
+            amplitude(self.ior, cos_substrate, substrate, cos_film),
+        );
+
+        (perpendicular + parallel) / 2.
+    }
+}
+
+// Reflected amplitude going from index `from` to index `to`, for perpendicular polarization.
+// Swapping the cosines gives the one for parallel polarization.
+fn amplitude(from: Float, cos_from: Float, to: Float, cos_to: Float) -> Float {
+    (from * cos_from - to * cos_to) / (from * cos_from + to * cos_to)
+}
+
+// Reflectance of a bare boundary, seen from index `from`.
+fn fresnel(from: Float, to: Float, cos: Float) -> Float {
+    let sin2 = (1. - cos * cos) * (from / to).powi(2);
+    if sin2 >= 1. {
+        return 1.;
+    }
+    let cos_to = (1. - sin2).sqrt();
+
+    let perpendicular = amplitude(from, cos, to, cos_to);
+    let parallel = amplitude(from, cos_to, to, cos);
+
+    (perpendicular * perpendicular + parallel * parallel) / 2.
+}
+
+impl Material for ThinFilm {
+    fn color(&self) -> RGB {
+        self.material.color()
+    }
+
+    fn texture_color(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.texture_color(surface)
+    }
+
+    fn shading_normal(&self, surface: &Surface) -> Option<Vec3> {
+        self.material.shading_normal(surface)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.material.emission()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn ior(&self) -> Float {
+        self.material.ior()
+    }
+
+    fn subsurface(&self) -> Option<Medium> {
+        self.material.subsurface()
+    }
+
+    fn cut_out(&self, surface: &Surface) -> bool {
+        self.material.cut_out(surface)
+    }
+
+    fn evaluate(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Vec3 {
+        self.material.evaluate(surface, to_viewer, to_light)
+    }
+
+    // Specular samples are picked again among the coated lobes.
+    fn sample(
+        &self,
+        surface: &Surface,
+        to_viewer: &Vec3,
+        random: (Float, Float),
+    ) -> Option<Sample> {
+        let sample = self.material.sample(surface, to_viewer, random)?;
+        if self.material.pdf(surface, to_viewer, &sample.direction) > 0. {
+            return Some(sample);
+        }
+
+        let lobes = self.specular(surface, to_viewer);
+        let weights: Vec<Float> = lobes.iter().map(|lobe| average(&lobe.weight)).collect();
+        let (index, probability, _) = pick_lobe(&weights, random.0)?;
+
+        Some(Sample {
+            direction: lobes[index].direction,
+            weight: lobes[index].weight / probability,
+        })
+    }
+
+    fn pdf(&self, surface: &Surface, to_viewer: &Vec3, to_light: &Vec3) -> Float {
+        self.material.pdf(surface, to_viewer, to_light)
+    }
+
+    // The coating's reflectance takes the place of the bare one in reflected lobes, refracted
+    // ones lose what it reflects more.
+    fn specular(&self, surface: &Surface, to_viewer: &Vec3) -> Vec<Sample> {
+        let (coated, bare) = self.reflectance(surface, to_viewer);
+        let outside = surface.facing(to_viewer);
+        let white = Vec3::new(1., 1., 1.);
+        let bare_vector = Vec3::new(bare, bare, bare);
+
+        self.material
+            .specular(surface, to_viewer)
+            .into_iter()
+            .map(|lobe| {
+                let weight = if lobe.direction.dot_product(&outside) > 0. {
+                    (lobe.weight + coated - bare_vector).max(&Vec3::zero())
+                } else {
+                    lobe.weight * (white - coated) / (1. - bare).max(Float::EPSILON)
+                };
+
+                Sample {
+                    direction: lobe.direction,
+                    weight,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::dielectric::Dielectric;
+    use crate::geometry::material::thin_film::ThinFilm;
+    use crate::geometry::material::{Material, Surface};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::rgb::RGB;
+
+    fn coated(substrate: Float, thickness: Float, ior: Float) -> ThinFilm {
+        ThinFilm {
+            material: Box::new(Dielectric {
+                color: RGB::new(255, 255, 255),
+                ior: substrate,
+                dispersion: None,
+                color_texture: None,
+            }),
+            thickness,
+            ior,
+        }
+    }
+
+    fn surface(wavelength: Option<Float>) -> Surface {
+        Surface {
+            normal: Vec3::new(0., 1., 0.),
+            tangent: Vec3::new(1., 0., 0.),
+            bitangent: Vec3::new(0., 0., -1.),
+            color: Vec3::new(1., 1., 1.),
+            uv: None,
+            footprint: 0.,
+            point: Vec3::zero(),
+            local_point: Vec3::zero(),
+            wavelength,
+        }
+    }
+
+    #[test]
+    fn quarter_wave_coating_cancels_reflection() {
+        let ior = (1.5 as Float).sqrt();
+        let coating = coated(1.5, 550. / (4. * ior), ior);
+        let up = Vec3::new(0., 1., 0.);
+
+        let lobes = coating.specular(&surface(Some(550.)), &up);
+        assert_approx_eq!(lobes[0].weight, Vec3::zero(), 1e-4);
+        assert_approx_eq!(lobes[1].weight, Vec3::new(1., 1., 1.), 1e-4);
+
+        // Other wavelengths are still reflected a little, more so at the ends of the spectrum.
+        let red = coating.specular(&surface(Some(700.)), &up)[0].weight.x;
+        assert!(red > 1e-3 && red < 0.04, "{}", red);
+    }
+
+    #[test]
+    fn soap_films_shimmer() {
+        let up = Vec3::new(0., 1., 0.);
+        let reflected =
+            |thickness| coated(1., thickness, 1.33).specular(&surface(None), &up)[0].weight;
+
+        // Far thinner than light waves, the film turns black.
+        assert!(reflected(5.).x < 1e-3);
+
+        let colors: Vec<Vec3> = [250., 350., 450.].iter().map(|&t| reflected(t)).collect();
+        for color in &colors {
+            assert!(color.x < 0.1 && color.y < 0.1 && color.z < 0.1);
+        }
+        assert!((colors[0] - colors[1]).length() > 0.01);
+        assert!((colors[1] - colors[2]).length() > 0.01);
+    }
+}
@@ -0,0 +1,316 @@
+use super::Float;
+use super::aabb::Aabb;
+use super::bvh::Bvh;
+use super::material::Material;
+use super::object::{Hit, Object, Uv};
+use super::ray::Ray;
+use super::triangle;
+use super::vec3::Vec3;
+use crate::math::mat4::Mat4;
+use crate::scene::light::{Emitter, Shape};
+use crate::utils::rgb::RGB;
+
+// Indexed triangle mesh. Per-vertex normals give smooth shading, otherwise every triangle is
+// shaded flat, and per-vertex colors replace the material color when present. Per-vertex texture
+// coordinates map textures onto it.
+pub struct Mesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub colors: Option<Vec<RGB>>,
+    pub uvs: Option<Vec<[Float; 2]>>,
+    pub triangles: Vec<[usize; 3]>,
+    pub material: Box<dyn Material>,
+
+    bvh: Bvh,
+    // Undoes the transforms applied so far, back to the coordinates the mesh was built with.
+    to_local: Mat4,
+}
+
+impl Mesh {
+    pub fn new(
+        vertices: Vec<Vec3>,
+        normals: Option<Vec<Vec3>>,
+        colors: Option<Vec<RGB>>,
+        triangles: Vec<[usize; 3]>,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let bvh = build_bvh(&vertices, &triangles);
+
+        Self {
+            vertices,
+            normals,
+            colors,
+            uvs: None,
+            triangles,
+            material,
+            bvh,
+            to_local: Mat4::identity(),
+        }
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<[Float; 2]>) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    // Scales the mesh uniformly around its local origin and then moves it to `position`.
+    pub fn placed(self, position: &Vec3, scale: Float) -> Self {
+        let scaling = Mat4::scaling(&Vec3::new(scale, scale, scale));
+
+        self.transformed(&(Mat4::translation(position) * scaling))
+    }
+
+    pub fn transformed(mut self, transform: &Mat4) -> Self {
+        for vertex in self.vertices.iter_mut() {
+            *vertex = transform.transform_point(vertex);
+        }
+        if let Some(normals) = self.normals.as_mut() {
+            for normal in normals.iter_mut() {
+                *normal = transform.transform_normal(normal);
+            }
+        }
+        self.bvh = build_bvh(&self.vertices, &self.triangles);
+        // A degenerate transform flattens the mesh, its texture space stays as it was.
+        if let Some(inverse) = transform.inverse() {
+            self.to_local = self.to_local * inverse;
+        }
+
+        self
+    }
+
+    fn interpolate_normal(&self, triangle: &[usize; 3], u: Float, v: Float) -> Vec3 {
+        let [a, b, c] = *triangle;
+
+        match &self.normals {
+            Some(normals) => {
+                (normals[a] * (1. - u - v) + normals[b] * u + normals[c] * v).normalize()
+            }
+            None => (self.vertices[b] - self.vertices[a])
+                .cross(&(self.vertices[c] - self.vertices[a]))
+                .normalize(),
+        }
+    }
+
+    fn interpolate_color(&self, triangle: &[usize; 3], u: Float, v: Float) -> Option<RGB> {
+        let [a, b, c] = *triangle;
+
+        self.colors.as_ref().map(|colors| {
+            let color = colors[a].as_vector() * (1. - u - v)
+                + colors[b].as_vector() * u
+                + colors[c].as_vector() * v;
+
+            RGB::from_vector(&color)
+        })
+    }
+
+    fn interpolate_uv(&self, triangle: &[usize; 3], u: Float, v: Float) -> Option<Uv> {
+        let [a, b, c] = *triangle;
+        let uvs = self.uvs.as_ref()?;
+        let (ua, ub, uc) = (uvs[a], uvs[b], uvs[c]);
+        let interpolate = |axis: usize| ua[axis] * (1. - u - v) + ub[axis] * u + uc[axis] * v;
+
+        // Both areas are doubled, the factor cancels out.
+        let uv_area =
+            ((ub[0] - ua[0]) * (uc[1] - ua[1]) - (ub[1] - ua[1]) * (uc[0] - ua[0])).abs();
+        let area = (self.vertices[b] - self.vertices[a])
+            .cross(&(self.vertices[c] - self.vertices[a]))
+            .length();
+
+        Some(Uv {
+            u: interpolate(0),
+            v: interpolate(1),
+            density: if area > 0. { (uv_area / area).sqrt() } else { 0. },
+        })
+    }
+
+    // Follows the texture coordinates across the triangle, kept perpendicular to the shading
+    // normal. The bitangent flips with mirrored texture coordinates.
+    fn tangent_frame(&self, triangle: &[usize; 3], normal: &Vec3) -> (Vec3, Vec3) {
+        let [a, b, c] = *triangle;
+        let uvs = match &self.uvs {
+            Some(uvs) => uvs,
+            None => return normal.orthonormal_basis(),
+        };
+
+        let edges = (
+            self.vertices[b] - self.vertices[a],
+            self.vertices[c] - self.vertices[a],
+        );
+        let (du1, dv1) = (uvs[b][0] - uvs[a][0], uvs[b][1] - uvs[a][1]);
+        let (du2, dv2) = (uvs[c][0] - uvs[a][0], uvs[c][1] - uvs[a][1]);
+        let determinant = du1 * dv2 - du2 * dv1;
+        let along_u = (edges.0 * dv2 - edges.1 * dv1) / determinant;
+        let along_v = (edges.1 * du1 - edges.0 * du2) / determinant;
+
+        let tangent = along_u - *normal * normal.dot_product(&along_u);
+        let length = tangent.length();
+        if !(length > 0. && length.is_finite()) {
+            return normal.orthonormal_basis();
+        }
+        let tangent = tangent / length;
+        let bitangent = normal.cross(&tangent);
+
+        if bitangent.dot_product(&along_v) < 0. {
+            (tangent, -bitangent)
+        } else {
+            (tangent, bitangent)
+        }
+    }
+}
+
+fn build_bvh(vertices: &[Vec3], triangles: &[[usize; 3]]) -> Bvh {
+    let bounds: Vec<Aabb> = triangles
+        .iter()
+        .map(|&[a, b, c]| Aabb::from_points(&[&vertices[a], &vertices[b], &vertices[c]]))
+        .collect();
+
+    Bvh::new(&bounds)
+}
+
+impl Object for Mesh {
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        let mut closest: Option<(usize, Float, Float, Float)> = None;
+
+        self.bvh
+            .traverse(&ray.origin, &ray.direction, ray.t, |index, t_max| {
+                let [a, b, c] = self.triangles[index];
+                let (t, u, v) = triangle::ray_intersect(
+                    &ray.origin,
+                    &ray.direction,
+                    &self.vertices[a],
+                    &self.vertices[b],
+                    &self.vertices[c],
+                )?;
+
+                if t >= t_max {
+                    return None;
+                }
+
+                closest = Some((index, t, u, v));
+                Some(t)
+            });
+
+        let (index, t, u, v) = closest?;
+        let triangle = &self.triangles[index];
+        ray.t = t;
+
+        let point = ray.origin + ray.direction * t;
+        let normal = self.interpolate_normal(triangle, u, v);
+        let (tangent, bitangent) = self.tangent_frame(triangle, &normal);
+
+        Some(Hit {
+            point,
+            local_point: self.to_local.transform_point(&point),
+            normal,
+            tangent,
+            bitangent,
+            color: self.interpolate_color(triangle, u, v),
+            uv: self.interpolate_uv(triangle, u, v),
+            primitive: index,
+        })
+    }
+
+    fn material(&self, _hit: &Hit) -> &dyn Material {
+        self.material.as_ref()
+    }
+
+    // One emitter per triangle, skipped altogether for meshes that don't glow.
+    fn emitters(&self) -> Vec<Emitter> {
+        let radiance = self.material.emission();
+        if radiance == Vec3::zero() {
+            return Vec::new();
+        }
+
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let vertices = [self.vertices[a], self.vertices[b], self.vertices[c]];
+                Emitter::new(Shape::Triangle(vertices), radiance)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::mesh::Mesh;
+    use crate::geometry::object::Object;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::rgb::RGB;
+
+    #[test]
+    fn intersect_closest() {
+        let material = Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        });
+        let vertices = vec![
+            Vec3::new(-1., -1., -5.),
+            Vec3::new(1., -1., -5.),
+            Vec3::new(0., 1., -5.),
+            Vec3::new(-1., -1., -3.),
+            Vec3::new(1., -1., -3.),
+            Vec3::new(0., 1., -3.),
+        ];
+        let colors = vec![RGB::new(255, 0, 0); 3]
+            .into_iter()
+            .chain(vec![RGB::new(0, 0, 255); 3])
+            .collect();
+        let mesh = Mesh::new(
+            vertices,
+            None,
+            Some(colors),
+            vec![[0, 1, 2], [3, 4, 5]],
+            material,
+        )
+        .placed(&Vec3::new(0., 0., -1.), 1.);
+        let mut ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.), Float::MAX);
+
+        let hit = mesh.intersect(&mut ray).unwrap();
+
+        assert_eq!(ray.t, 4.);
+        assert_eq!(hit.normal.z, 1.);
+        assert_approx_eq!(hit.local_point, Vec3::new(0., 0., -3.));
+        assert_eq!(hit.color.unwrap().b, 255);
+        assert_eq!(hit.uv, None);
+    }
+
+    #[test]
+    fn interpolate_uvs() {
+        let material = Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        });
+        // A 4x2 quad with the whole texture stretched over it.
+        let vertices = vec![
+            Vec3::new(-2., -1., -5.),
+            Vec3::new(2., -1., -5.),
+            Vec3::new(2., 1., -5.),
+            Vec3::new(-2., 1., -5.),
+        ];
+        let mesh = Mesh::new(vertices, None, None, vec![[0, 1, 2], [0, 2, 3]], material)
+            .with_uvs(vec![[0., 1.], [1., 1.], [1., 0.], [0., 0.]]);
+        let mut ray = Ray::new(Vec3::new(1., 0.5, 0.), Vec3::new(0., 0., -1.), Float::MAX);
+
+        let hit = mesh.intersect(&mut ray).unwrap();
+        let uv = hit.uv.unwrap();
+
+        assert_approx_eq!(uv.u, 0.75);
+        assert_approx_eq!(uv.v, 0.25);
+        assert_approx_eq!(uv.density, Float::sqrt(1. / 8.));
+        // The texture is upright, so v grows downwards.
+        assert_approx_eq!(hit.tangent, Vec3::new(1., 0., 0.));
+        assert_approx_eq!(hit.bitangent, Vec3::new(0., -1., 0.));
+    }
+}
@@ -0,0 +1,258 @@
+use super::Float;
+use super::material::Material;
+use super::object::{Hit, Object};
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+const MARCH_STEPS_PER_RADIUS: Float = 16.;
+const BISECTION_STEPS: usize = 24;
+
+// A single field source. Its contribution falls off as (1 - r^2 / R^2)^3 and is exactly zero
+// beyond `radius`, which lets the surface be bounded by the union of the source spheres.
+pub struct Metaball {
+    pub center: Vec3,
+    pub radius: Float,
+    pub strength: Float,
+}
+
+impl Metaball {
+    pub fn new(center: Vec3, radius: Float, strength: Float) -> Self {
+        Self {
+            center,
+            radius,
+            strength,
+        }
+    }
+
+    fn falloff(&self, point: &Vec3) -> Float {
+        let offset = *point - self.center;
+        let q = offset.dot_product(&offset) / (self.radius * self.radius);
+
+        if q >= 1. {
+            0.
+        } else {
+            self.strength * (1. - q).powi(3)
+        }
+    }
+
+    fn gradient(&self, point: &Vec3) -> Vec3 {
+        let offset = *point - self.center;
+        let radius_sqr = self.radius * self.radius;
+        let q = offset.dot_product(&offset) / radius_sqr;
+
+        if q >= 1. {
+            Vec3::zero()
+        } else {
+            offset * (-6. * self.strength * (1. - q).powi(2) / radius_sqr)
+        }
+    }
+
+    fn ray_interval(&self, ray: &Ray) -> Option<(Float, Float)> {
+        let oc = ray.origin - self.center;
+        let a = ray.direction.dot_product(&ray.direction);
+        let b = oc.dot_product(&ray.direction);
+        let c = oc.dot_product(&oc) - self.radius * self.radius;
+        let discriminant = b * b - a * c;
+
+        if discriminant < 0. {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        Some(((-b - root) / a, (-b + root) / a))
+    }
+}
+
+// The blobby surface where the summed field of all sources reaches `threshold`.
+pub struct Metaballs {
+    pub balls: Vec<Metaball>,
+    pub threshold: Float,
+    pub material: Box<dyn Material>,
+}
+
+impl Metaballs {
+    pub fn new(balls: Vec<Metaball>, threshold: Float, material: Box<dyn Material>) -> Self {
+        Self {
+            balls,
+            threshold,
+            material,
+        }
+    }
+
+    pub fn field(&self, point: &Vec3) -> Float {
+        self.balls.iter().map(|ball| ball.falloff(point)).sum()
+    }
+
+    pub fn normal_at(&self, point: &Vec3) -> Vec3 {
+        let gradient = self
+            .balls
+            .iter()
+            .fold(Vec3::zero(), |sum, ball| sum + ball.gradient(point));
+
+        // The field decreases outwards, so the outward normal is against the gradient.
+        (-gradient).normalize()
+    }
+
+    fn signed_field(&self, ray: &Ray, t: Float) -> Float {
+        self.field(&(ray.origin + ray.direction * t)) - self.threshold
+    }
+
+    // Merged, sorted parameter ranges where at least one source influences the ray.
+    fn influence_intervals(&self, ray: &Ray) -> Vec<(Float, Float, Float)> {
+        let mut intervals: Vec<(Float, Float, Float)> = self
+            .balls
+            .iter()
+            .filter_map(|ball| {
+                let (t0, t1) = ball.ray_interval(ray)?;
+                let (t0, t1) = (t0.max(0.), t1.min(ray.t));
+
+                if t0 < t1 {
+                    Some((t0, t1, ball.radius))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        intervals.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut merged: Vec<(Float, Float, Float)> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.0 <= last.1 => {
+                    last.1 = last.1.max(interval.1);
+                    last.2 = last.2.min(interval.2);
+                }
+                _ => merged.push(interval),
+            }
+        }
+
+        merged
+    }
+
+    // Marches through each interval in steps well below the smallest source radius and
+    // refines the first sign change of the field by bisection.
+    fn find_root(&self, ray: &Ray) -> Option<Float> {
+        let direction_length = ray.direction.length();
+
+        for (start, end, min_radius) in self.influence_intervals(ray) {
+            let step = min_radius / (MARCH_STEPS_PER_RADIUS * direction_length);
+            let mut t0 = start;
+            let mut value0 = self.signed_field(ray, t0);
+
+            while t0 < end {
+                let t1 = (t0 + step).min(end);
+                let value1 = self.signed_field(ray, t1);
+
+                if value0.signum() != value1.signum() {
+                    let (mut low, mut high) = (t0, t1);
+
+                    for _ in 0..BISECTION_STEPS {
+                        let middle = (low + high) / 2.;
+                        if self.signed_field(ray, middle).signum() == value0.signum() {
+                            low = middle;
+                        } else {
+                            high = middle;
+                        }
+                    }
+
+                    return Some((low + high) / 2.);
+                }
+
+                t0 = t1;
+                value0 = value1;
+            }
+        }
+
+        None
+    }
+}
+
+impl Object for Metaballs {
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        let t = self.find_root(ray)?;
+        ray.t = t;
+
+        let point = ray.origin + ray.direction * t;
+        let normal = self.normal_at(&point);
+        let (tangent, bitangent) = normal.orthonormal_basis();
+
+        // The balls move independently, so the blob has no frame of its own.
+        Some(Hit {
+            point,
+            local_point: point,
+            normal,
+            tangent,
+            bitangent,
+            color: None,
+            uv: None,
+            primitive: 0,
+        })
+    }
+
+    fn material(&self, _hit: &Hit) -> &dyn Material {
+        self.material.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::Material;
+    use crate::geometry::metaball::{Metaball, Metaballs};
+    use crate::geometry::object::Object;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::rgb::RGB;
+
+    fn material() -> Box<dyn Material> {
+        Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        })
+    }
+
+    #[test]
+    fn intersect_single() {
+        let threshold: Float = 0.125;
+        let blob = Metaballs::new(
+            vec![Metaball::new(Vec3::new(0., 0., -10.), 2., 1.)],
+            threshold,
+            material(),
+        );
+        // (1 - r^2 / R^2)^3 = 0.125 gives r^2 = R^2 / 2.
+        let expected_radius = Float::sqrt(2.);
+        let mut ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., -1.), Float::MAX);
+
+        let hit = blob.intersect(&mut ray).unwrap();
+
+        assert_approx_eq!(ray.t, 10. - expected_radius, 1e-4);
+        assert_approx_eq!(hit.normal, Vec3::new(0., 0., 1.), 1e-4);
+    }
+
+    #[test]
+    fn intersect_blended() {
+        let blob = Metaballs::new(
+            vec![
+                Metaball::new(Vec3::new(-1.2, 0., 0.), 2., 1.),
+                Metaball::new(Vec3::new(1.2, 0., 0.), 2., 1.),
+            ],
+            0.125,
+            material(),
+        );
+        // Each source alone ends 1.41 units from its center, the sum bridges the gap.
+        let mut ray = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.), Float::MAX);
+
+        let hit = blob.intersect(&mut ray).unwrap();
+
+        assert!(hit.point.y > 0.);
+        assert_approx_eq!(hit.normal, Vec3::new(0., 1., 0.), 1e-4);
+
+        let mut miss = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., 1., 0.), Float::MAX);
+        assert!(blob.intersect(&mut miss).is_none());
+    }
+}
@@ -1,4 +1,24 @@
+pub mod aabb;
+pub mod bvh;
+pub mod heightfield;
+pub mod material;
+pub mod mesh;
+pub mod metaball;
+pub mod motion;
+pub mod object;
 pub mod ray;
 pub mod sphere;
+pub mod sphere_set;
+pub mod triangle;
 pub mod vec3;
-pub mod material;
\ No newline at end of file
+
+// Scalar type of all geometry; the `f64` feature trades speed for precision in scenes with very
+// large or very small coordinates.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
@@ -0,0 +1,100 @@
+use super::Float;
+use super::material::Material;
+use super::object::{Hit, Object};
+use super::ray::Ray;
+use super::vec3::Vec3;
+use crate::scene::light::Emitter;
+
+// Moves any object linearly by `displacement` while the shutter is open, which blurs it
+// once several time samples are taken per pixel.
+pub struct Moving {
+    pub object: Box<dyn Object>,
+    pub displacement: Vec3,
+}
+
+impl Moving {
+    pub fn new(object: Box<dyn Object>, displacement: Vec3) -> Self {
+        Self {
+            object,
+            displacement,
+        }
+    }
+
+    pub fn offset_at(&self, time: Float) -> Vec3 {
+        self.displacement * time
+    }
+}
+
+impl Object for Moving {
+    // Moving the ray backwards is the same as moving the object forwards.
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        let offset = self.offset_at(ray.time);
+        let mut local_ray = *ray;
+        local_ray.origin = ray.origin - offset;
+
+        let hit = self.object.intersect(&mut local_ray)?;
+        ray.t = local_ray.t;
+
+        Some(Hit {
+            point: hit.point + offset,
+            local_point: hit.local_point,
+            normal: hit.normal,
+            tangent: hit.tangent,
+            bitangent: hit.bitangent,
+            color: hit.color,
+            uv: hit.uv,
+            primitive: hit.primitive,
+        })
+    }
+
+    fn material(&self, hit: &Hit) -> &dyn Material {
+        self.object.material(hit)
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        self.object
+            .emitters()
+            .into_iter()
+            .map(|emitter| Emitter {
+                displacement: emitter.displacement + self.displacement,
+                ..emitter
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::motion::Moving;
+    use crate::geometry::object::Object;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::vec3::Vec3;
+    use crate::utils::rgb::RGB;
+
+    #[test]
+    fn intersect_at_time() {
+        let material = Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        });
+        let sphere = Sphere::new(Vec3::new(0., 0., -10.), 1., material);
+        let moving = Moving::new(Box::new(sphere), Vec3::new(4., 0., 0.));
+        let ray = || Ray::new(Vec3::new(4., 0., 0.), Vec3::new(0., 0., -1.), Float::MAX);
+
+        assert!(moving.intersect(&mut ray()).is_none());
+
+        let mut late_ray = ray().at_time(1.);
+        let hit = moving.intersect(&mut late_ray).unwrap();
+
+        assert_eq!(late_ray.t, 9.);
+        assert_eq!(hit.point.x, 4.);
+        assert_eq!(hit.normal.z, 1.);
+    }
+}
@@ -0,0 +1,48 @@
+use super::Float;
+use super::material::Material;
+use super::ray::Ray;
+use super::vec3::Vec3;
+use crate::scene::light::Emitter;
+use crate::utils::rgb::RGB;
+
+pub struct Hit {
+    pub point: Vec3,
+    // The same point in the object's own frame, where it stays put when the object moves.
+    pub local_point: Vec3,
+    pub normal: Vec3,
+    // Unit directions along which u and v grow, perpendicular to the normal. Any such pair for
+    // objects without texture coordinates.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    // Overrides the material color, e.g. for meshes with vertex colors.
+    pub color: Option<RGB>,
+    // Texture coordinates, None for objects without a parameterization.
+    pub uv: Option<Uv>,
+    // Which part of a compound object was hit, e.g. a triangle of a mesh.
+    pub primitive: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uv {
+    pub u: Float,
+    pub v: Float,
+    // How fast the coordinates change across the surface, per world unit. Tells how much of the
+    // texture a ray of a given width covers.
+    pub density: Float,
+}
+
+pub trait Object: Send + Sync {
+    // Returns the hit only when it is closer than `ray.t`, shortening `ray.t` to it.
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit>;
+    // Same as `intersect` for each ray of a coherent packet, e.g. primary rays of neighbouring
+    // pixels. Objects that can test several rays at once override it.
+    fn intersect_packet(&self, rays: &mut [Ray]) -> Vec<Option<Hit>> {
+        rays.iter_mut().map(|ray| self.intersect(ray)).collect()
+    }
+    fn material(&self, hit: &Hit) -> &dyn Material;
+    // Parts of the object whose material glows, sampled to light the rest of the scene. Objects
+    // that can't be sampled only glow where they're seen, directly or through mirrors.
+    fn emitters(&self) -> Vec<Emitter> {
+        Vec::new()
+    }
+}
@@ -1,13 +1,45 @@
+use super::Float;
 use super::vec3::{Vec3};
 
+#[derive(Clone, Copy)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
-    pub t: f32,
+    pub t: Float,
+    // Moment within the shutter interval, from 0 (open) to 1 (closed).
+    pub time: Float,
+    // The ray as a cone for texture filtering: its width at the origin and how much it grows
+    // per unit of distance.
+    pub width: Float,
+    pub spread: Float,
+    // In nanometers, for rays carrying a single band of the spectrum after dispersive glass split
+    // white light. None for rays carrying all of it.
+    pub wavelength: Option<Float>,
 }
 
 impl Ray {
-    pub fn new(origin: Vec3, direction: Vec3, t: f32) -> Self {
-        Self { origin, direction, t }
+    pub fn new(origin: Vec3, direction: Vec3, t: Float) -> Self {
+        Self { origin, direction, t, time: 0., width: 0., spread: 0., wavelength: None }
     }
-}
\ No newline at end of file
+
+    pub fn at_time(mut self, time: Float) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_cone(mut self, width: Float, spread: Float) -> Self {
+        self.width = width;
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_wavelength(mut self, wavelength: Option<Float>) -> Self {
+        self.wavelength = wavelength;
+        self
+    }
+
+    // Width of the cone at `t`, i.e. at the hit once an intersection has shortened the ray.
+    pub fn footprint(&self) -> Float {
+        self.width + self.spread * self.t
+    }
+}
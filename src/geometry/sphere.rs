@@ -1,17 +1,21 @@
+use super::Float;
+use super::consts::PI;
 use super::material::Material;
+use super::object::{Hit, Object, Uv};
 use super::ray::Ray;
 use super::vec3::Vec3;
+use crate::scene::light::{Emitter, Shape};
 
 pub struct Sphere {
     pub center: Vec3,
-    pub radius: f32,
-    pub material: Material,
+    pub radius: Float,
+    pub material: Box<dyn Material>,
 
-    radius_sqr: f32,
+    radius_sqr: Float,
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f32, material: Material) -> Self {
+    pub fn new(center: Vec3, radius: Float, material: Box<dyn Material>) -> Self {
         Self {
             center,
             radius,
@@ -23,13 +27,10 @@ impl Sphere {
     pub fn ray_intersect(&self, ray: &mut Ray) -> bool {
         let orig = &ray.origin;
         let dir = &ray.direction;
-        let c = self.center.minus(orig);
+        let c = self.center - *orig;
 
+        // The center may be behind rays starting inside the sphere.
         let tca = c.dot_product(dir);
-        if tca < 0. {
-            return false;
-        }
-
         let projection = c.dot_product(&c) - tca * tca;
 
         if projection > self.radius_sqr {
@@ -43,7 +44,7 @@ impl Sphere {
             t0 = t1;
         }
 
-        if t0 < 0. {
+        if t0 < 0. || t0 >= ray.t {
             return false;
         }
 
@@ -53,29 +54,112 @@ impl Sphere {
     }
 }
 
+// Longitude along u and latitude along v, from the north pole at the top of the texture. The
+// seam faces -x.
+pub fn spherical_uv(normal: &Vec3, radius: Float) -> Uv {
+    Uv {
+        u: 0.5 + normal.z.atan2(normal.x) / (2. * PI),
+        v: normal.y.clamp(-1., 1.).acos() / PI,
+        density: 1. / (2. * PI * radius),
+    }
+}
+
+// Along the parallel towards growing longitude, then along the meridian towards the south pole.
+// The poles get an arbitrary pair.
+pub fn spherical_tangents(normal: &Vec3) -> (Vec3, Vec3) {
+    let parallel = Vec3::new(-normal.z, 0., normal.x);
+    if parallel.length() < 1e-6 {
+        return normal.orthonormal_basis();
+    }
+    let tangent = parallel.normalize();
+
+    (tangent, normal.cross(&tangent))
+}
+
+impl Object for Sphere {
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        if !self.ray_intersect(ray) {
+            return None;
+        }
+
+        let point = ray.origin + ray.direction * ray.t;
+        let normal = (point - self.center).normalize();
+        let (tangent, bitangent) = spherical_tangents(&normal);
+
+        Some(Hit {
+            point,
+            local_point: point - self.center,
+            normal,
+            tangent,
+            bitangent,
+            color: None,
+            uv: Some(spherical_uv(&normal, self.radius)),
+            primitive: 0,
+        })
+    }
+
+    fn material(&self, _hit: &Hit) -> &dyn Material {
+        self.material.as_ref()
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        let shape = Shape::Sphere {
+            center: self.center,
+            radius: self.radius,
+        };
+
+        vec![Emitter::new(shape, self.material.emission())]
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::geometry::material::Material;
+    use crate::geometry::Float;
+    use crate::geometry::material::phong::Phong;
     use crate::geometry::ray::Ray;
-    use crate::geometry::sphere::Sphere;
-    use crate::geometry::vec3::Vec3;
+    use crate::geometry::consts::PI;
+    use crate::geometry::sphere::{spherical_tangents, spherical_uv, Sphere};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
     use crate::utils::rgb::RGB;
 
     #[test]
     fn ray_intersect() {
         let orig = Vec3::new(0., 0., 0.);
         let dir = Vec3::new(4., 0., 0.).normalize();
-        let mut ray = Ray::new(orig, dir, std::f32::MAX);
+        let mut ray = Ray::new(orig, dir, Float::MAX);
 
-        let material = Material {
+        let material = Box::new(Phong {
             color: RGB::new(24, 24, 24),
             specular_exponent: 1.,
             albedo: (1., 1., 1., 1.),
             refractive_index: 0.,
-        };
+            color_texture: None,
+            specular_texture: None,
+        });
 
         let sphere = Sphere::new(Vec3::new(4., 1., 0.), 2.5, material);
 
         assert!(sphere.ray_intersect(&mut ray));
     }
+
+    #[test]
+    fn uv_mapping() {
+        let north = spherical_uv(&Vec3::new(0., 1., 0.), 2.);
+        let east = spherical_uv(&Vec3::new(1., 0., 0.), 2.);
+        let south_front = spherical_uv(&Vec3::new(0., -1., 1.).normalize(), 2.);
+
+        assert_approx_eq!(north.v, 0.);
+        assert_approx_eq!(east.u, 0.5);
+        assert_approx_eq!(east.v, 0.5);
+        assert_approx_eq!(south_front.u, 0.75);
+        assert_approx_eq!(south_front.v, 0.75);
+        // The equator is 4 pi long and spans u once.
+        assert_approx_eq!(east.density, 1. / (4. * PI));
+
+        let (tangent, bitangent) = spherical_tangents(&Vec3::new(1., 0., 0.));
+        assert_approx_eq!(tangent, Vec3::new(0., 0., 1.));
+        assert_approx_eq!(bitangent, Vec3::new(0., -1., 0.));
+        let (tangent, bitangent) = spherical_tangents(&Vec3::new(0., 1., 0.));
+        assert_approx_eq!(tangent.dot_product(&bitangent), 0.);
+    }
 }
@@ -0,0 +1,691 @@
+use super::Float;
+use super::material::Material;
+use super::object::{Hit, Object};
+use super::ray::Ray;
+use super::sphere::{spherical_tangents, spherical_uv, Sphere};
+use super::vec3::Vec3;
+use crate::scene::light::{Emitter, Shape};
+
+// Widest batch used by any implementation; the arrays are padded to a multiple of it.
+const LANES: usize = 8;
+// Padding spheres have a NaN squared radius: every comparison with it fails, so no ray hits them.
+// A negative one isn't enough, rounding lets far away rays through.
+const PADDING_RADIUS_SQR: Float = Float::NAN;
+
+// Spheres stored as a structure of arrays so that several of them can be tested against a
+// ray at once with SIMD. Produces the same hits as testing every `Sphere` one by one.
+pub struct SphereSet {
+    centers_x: Vec<Float>,
+    centers_y: Vec<Float>,
+    centers_z: Vec<Float>,
+    radii_sqr: Vec<Float>,
+    materials: Vec<Box<dyn Material>>,
+}
+
+impl SphereSet {
+    pub fn new() -> Self {
+        Self {
+            centers_x: Vec::new(),
+            centers_y: Vec::new(),
+            centers_z: Vec::new(),
+            radii_sqr: Vec::new(),
+            materials: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sphere: Sphere) {
+        let index = self.materials.len();
+
+        if index == self.radii_sqr.len() {
+            let padded = index + LANES;
+            self.centers_x.resize(padded, 0.);
+            self.centers_y.resize(padded, 0.);
+            self.centers_z.resize(padded, 0.);
+            self.radii_sqr.resize(padded, PADDING_RADIUS_SQR);
+        }
+
+        self.centers_x[index] = sphere.center.x;
+        self.centers_y[index] = sphere.center.y;
+        self.centers_z[index] = sphere.center.z;
+        self.radii_sqr[index] = sphere.radius * sphere.radius;
+        self.materials.push(sphere.material);
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    pub fn center(&self, index: usize) -> Vec3 {
+        Vec3::new(
+            self.centers_x[index],
+            self.centers_y[index],
+            self.centers_z[index],
+        )
+    }
+
+    // Index and distance of the closest sphere hit before `ray.t`, using the widest
+    // instruction set the CPU supports.
+    pub fn nearest(&self, ray: &Ray) -> Option<(usize, Float)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+        {
+            if is_x86_feature_detected!("avx") {
+                return unsafe { x86::nearest_avx(self, ray) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { x86::nearest_sse(self, ray) };
+            }
+        }
+
+        self.nearest_scalar(ray)
+    }
+
+    // `nearest` for every ray of a packet, testing several rays against one sphere at a time.
+    pub fn nearest_packet(&self, rays: &[Ray]) -> Vec<Option<(usize, Float)>> {
+        if self.is_empty() {
+            return vec![None; rays.len()];
+        }
+
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+        {
+            if is_x86_feature_detected!("avx") {
+                return unsafe { x86::nearest_packet_avx(self, rays) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { x86::nearest_packet_sse(self, rays) };
+            }
+        }
+
+        rays.iter().map(|ray| self.nearest_scalar(ray)).collect()
+    }
+
+    // Same steps as `Sphere::ray_intersect`, one sphere at a time.
+    pub fn nearest_scalar(&self, ray: &Ray) -> Option<(usize, Float)> {
+        let mut nearest = None;
+        let mut t_max = ray.t;
+
+        for index in 0..self.len() {
+            let c = self.center(index) - ray.origin;
+            let tca = c.dot_product(&ray.direction);
+            let projection = c.dot_product(&c) - tca * tca;
+            let thc_sqr = self.radii_sqr[index] - projection;
+
+            if thc_sqr < 0. {
+                continue;
+            }
+
+            let thc = thc_sqr.sqrt();
+            let t = if tca - thc < 0. { tca + thc } else { tca - thc };
+
+            if t >= 0. && t < t_max {
+                t_max = t;
+                nearest = Some((index, t));
+            }
+        }
+
+        nearest
+    }
+}
+
+impl SphereSet {
+    fn hit(&self, ray: &mut Ray, index: usize, t: Float) -> Hit {
+        ray.t = t;
+
+        let point = ray.origin + ray.direction * t;
+        let local_point = point - self.center(index);
+        let normal = local_point.normalize();
+        let (tangent, bitangent) = spherical_tangents(&normal);
+
+        Hit {
+            point,
+            local_point,
+            normal,
+            tangent,
+            bitangent,
+            color: None,
+            uv: Some(spherical_uv(&normal, self.radii_sqr[index].sqrt())),
+            primitive: index,
+        }
+    }
+}
+
+impl Default for SphereSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Object for SphereSet {
+    fn intersect(&self, ray: &mut Ray) -> Option<Hit> {
+        let (index, t) = self.nearest(ray)?;
+
+        Some(self.hit(ray, index, t))
+    }
+
+    fn intersect_packet(&self, rays: &mut [Ray]) -> Vec<Option<Hit>> {
+        let nearest = self.nearest_packet(rays);
+
+        rays.iter_mut()
+            .zip(nearest)
+            .map(|(ray, nearest)| nearest.map(|(index, t)| self.hit(ray, index, t)))
+            .collect()
+    }
+
+    fn material(&self, hit: &Hit) -> &dyn Material {
+        self.materials[hit.primitive].as_ref()
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        (0..self.len())
+            .map(|index| {
+                let shape = Shape::Sphere {
+                    center: self.center(index),
+                    radius: self.radii_sqr[index].sqrt(),
+                };
+
+                Emitter::new(shape, self.materials[index].emission())
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::SphereSet;
+    use crate::geometry::ray::Ray;
+
+    // Lane indices are carried as floats, exact for up to 2^24 spheres.
+    fn reduce(best_t: &[f32], best_index: &[f32]) -> Option<(usize, f32)> {
+        let mut nearest: Option<(usize, f32)> = None;
+
+        for (&t, &index) in best_t.iter().zip(best_index) {
+            if index < 0. {
+                continue;
+            }
+
+            let index = index as usize;
+            let closer = match nearest {
+                Some((nearest_index, nearest_t)) => {
+                    t < nearest_t || (t == nearest_t && index < nearest_index)
+                }
+                None => true,
+            };
+            if closer {
+                nearest = Some((index, t));
+            }
+        }
+
+        nearest
+    }
+
+    // One value per ray of a packet; lanes past the last ray get `padding`.
+    fn gather(rays: &[Ray], padding: f32, value: impl Fn(&Ray) -> f32) -> [f32; 8] {
+        let mut lanes = [padding; 8];
+        for (lane, ray) in lanes.iter_mut().zip(rays) {
+            *lane = value(ray);
+        }
+
+        lanes
+    }
+
+    fn unpack(best_t: &[f32], best_index: &[f32], count: usize) -> Vec<Option<(usize, f32)>> {
+        best_t
+            .iter()
+            .zip(best_index)
+            .take(count)
+            .map(|(&t, &index)| {
+                if index < 0. {
+                    None
+                } else {
+                    Some((index as usize, t))
+                }
+            })
+            .collect()
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn nearest_sse(spheres: &SphereSet, ray: &Ray) -> Option<(usize, f32)> {
+        let select = |mask: __m128, a: __m128, b: __m128| {
+            _mm_or_ps(_mm_and_ps(mask, a), _mm_andnot_ps(mask, b))
+        };
+
+        let origin_x = _mm_set1_ps(ray.origin.x);
+        let origin_y = _mm_set1_ps(ray.origin.y);
+        let origin_z = _mm_set1_ps(ray.origin.z);
+        let direction_x = _mm_set1_ps(ray.direction.x);
+        let direction_y = _mm_set1_ps(ray.direction.y);
+        let direction_z = _mm_set1_ps(ray.direction.z);
+        let zero = _mm_setzero_ps();
+
+        let mut best_t = _mm_set1_ps(ray.t);
+        let mut best_index = _mm_set1_ps(-1.);
+        let mut index = _mm_setr_ps(0., 1., 2., 3.);
+        let step = _mm_set1_ps(4.);
+
+        for start in (0..spheres.len()).step_by(4) {
+            let cx = _mm_sub_ps(_mm_loadu_ps(&spheres.centers_x[start]), origin_x);
+            let cy = _mm_sub_ps(_mm_loadu_ps(&spheres.centers_y[start]), origin_y);
+            let cz = _mm_sub_ps(_mm_loadu_ps(&spheres.centers_z[start]), origin_z);
+            let radius_sqr = _mm_loadu_ps(&spheres.radii_sqr[start]);
+
+            let tca = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(cx, direction_x), _mm_mul_ps(cy, direction_y)),
+                _mm_mul_ps(cz, direction_z),
+            );
+            let c_sqr = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(cx, cx), _mm_mul_ps(cy, cy)),
+                _mm_mul_ps(cz, cz),
+            );
+            let thc_sqr = _mm_sub_ps(radius_sqr, _mm_sub_ps(c_sqr, _mm_mul_ps(tca, tca)));
+            let thc = _mm_sqrt_ps(_mm_max_ps(thc_sqr, zero));
+
+            let t0 = _mm_sub_ps(tca, thc);
+            let t = select(_mm_cmplt_ps(t0, zero), _mm_add_ps(tca, thc), t0);
+
+            let hit = _mm_and_ps(
+                _mm_cmpge_ps(thc_sqr, zero),
+                _mm_and_ps(_mm_cmpge_ps(t, zero), _mm_cmplt_ps(t, best_t)),
+            );
+            best_t = select(hit, t, best_t);
+            best_index = select(hit, index, best_index);
+            index = _mm_add_ps(index, step);
+        }
+
+        let (mut t, mut indices) = ([0f32; 4], [0f32; 4]);
+        _mm_storeu_ps(t.as_mut_ptr(), best_t);
+        _mm_storeu_ps(indices.as_mut_ptr(), best_index);
+
+        reduce(&t, &indices)
+    }
+
+    #[target_feature(enable = "avx")]
+    pub unsafe fn nearest_avx(spheres: &SphereSet, ray: &Ray) -> Option<(usize, f32)> {
+        let origin_x = _mm256_set1_ps(ray.origin.x);
+        let origin_y = _mm256_set1_ps(ray.origin.y);
+        let origin_z = _mm256_set1_ps(ray.origin.z);
+        let direction_x = _mm256_set1_ps(ray.direction.x);
+        let direction_y = _mm256_set1_ps(ray.direction.y);
+        let direction_z = _mm256_set1_ps(ray.direction.z);
+        let zero = _mm256_setzero_ps();
+
+        let mut best_t = _mm256_set1_ps(ray.t);
+        let mut best_index = _mm256_set1_ps(-1.);
+        let mut index = _mm256_setr_ps(0., 1., 2., 3., 4., 5., 6., 7.);
+        let step = _mm256_set1_ps(8.);
+
+        for start in (0..spheres.len()).step_by(8) {
+            let cx = _mm256_sub_ps(_mm256_loadu_ps(&spheres.centers_x[start]), origin_x);
+            let cy = _mm256_sub_ps(_mm256_loadu_ps(&spheres.centers_y[start]), origin_y);
+            let cz = _mm256_sub_ps(_mm256_loadu_ps(&spheres.centers_z[start]), origin_z);
+            let radius_sqr = _mm256_loadu_ps(&spheres.radii_sqr[start]);
+
+            let tca = _mm256_add_ps(
+                _mm256_add_ps(
+                    _mm256_mul_ps(cx, direction_x),
+                    _mm256_mul_ps(cy, direction_y),
+                ),
+                _mm256_mul_ps(cz, direction_z),
+            );
+            let c_sqr = _mm256_add_ps(
+                _mm256_add_ps(_mm256_mul_ps(cx, cx), _mm256_mul_ps(cy, cy)),
+                _mm256_mul_ps(cz, cz),
+            );
+            let thc_sqr = _mm256_sub_ps(radius_sqr, _mm256_sub_ps(c_sqr, _mm256_mul_ps(tca, tca)));
+            let thc = _mm256_sqrt_ps(_mm256_max_ps(thc_sqr, zero));
+
+            let t0 = _mm256_sub_ps(tca, thc);
+            let t = _mm256_blendv_ps(
+                t0,
+                _mm256_add_ps(tca, thc),
+                _mm256_cmp_ps(t0, zero, _CMP_LT_OQ),
+            );
+
+            let hit = _mm256_and_ps(
+                _mm256_cmp_ps(thc_sqr, zero, _CMP_GE_OQ),
+                _mm256_and_ps(
+                    _mm256_cmp_ps(t, zero, _CMP_GE_OQ),
+                    _mm256_cmp_ps(t, best_t, _CMP_LT_OQ),
+                ),
+            );
+            best_t = _mm256_blendv_ps(best_t, t, hit);
+            best_index = _mm256_blendv_ps(best_index, index, hit);
+            index = _mm256_add_ps(index, step);
+        }
+
+        let (mut t, mut indices) = ([0f32; 8], [0f32; 8]);
+        _mm256_storeu_ps(t.as_mut_ptr(), best_t);
+        _mm256_storeu_ps(indices.as_mut_ptr(), best_index);
+
+        reduce(&t, &indices)
+    }
+
+    // Lanes hold rays instead of spheres: every sphere is broadcast and tested against four
+    // rays at once. Spheres are visited in order, so ties resolve like the scalar path.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn nearest_packet_sse(
+        spheres: &SphereSet,
+        rays: &[Ray],
+    ) -> Vec<Option<(usize, f32)>> {
+        let select = |mask: __m128, a: __m128, b: __m128| {
+            _mm_or_ps(_mm_and_ps(mask, a), _mm_andnot_ps(mask, b))
+        };
+        let zero = _mm_setzero_ps();
+        let mut nearest = Vec::with_capacity(rays.len());
+
+        for rays in rays.chunks(4) {
+            let origin_x = _mm_loadu_ps(gather(rays, 0., |ray| ray.origin.x).as_ptr());
+            let origin_y = _mm_loadu_ps(gather(rays, 0., |ray| ray.origin.y).as_ptr());
+            let origin_z = _mm_loadu_ps(gather(rays, 0., |ray| ray.origin.z).as_ptr());
+            let direction_x = _mm_loadu_ps(gather(rays, 0., |ray| ray.direction.x).as_ptr());
+            let direction_y = _mm_loadu_ps(gather(rays, 0., |ray| ray.direction.y).as_ptr());
+            let direction_z = _mm_loadu_ps(gather(rays, 0., |ray| ray.direction.z).as_ptr());
+
+            // Padding lanes start with t = 0, which no hit can beat.
+            let mut best_t = _mm_loadu_ps(gather(rays, 0., |ray| ray.t).as_ptr());
+            let mut best_index = _mm_set1_ps(-1.);
+
+            for index in 0..spheres.len() {
+                let cx = _mm_sub_ps(_mm_set1_ps(spheres.centers_x[index]), origin_x);
+                let cy = _mm_sub_ps(_mm_set1_ps(spheres.centers_y[index]), origin_y);
+                let cz = _mm_sub_ps(_mm_set1_ps(spheres.centers_z[index]), origin_z);
+                let radius_sqr = _mm_set1_ps(spheres.radii_sqr[index]);
+
+                let tca = _mm_add_ps(
+                    _mm_add_ps(_mm_mul_ps(cx, direction_x), _mm_mul_ps(cy, direction_y)),
+                    _mm_mul_ps(cz, direction_z),
+                );
+                let c_sqr = _mm_add_ps(
+                    _mm_add_ps(_mm_mul_ps(cx, cx), _mm_mul_ps(cy, cy)),
+                    _mm_mul_ps(cz, cz),
+                );
+                let thc_sqr = _mm_sub_ps(radius_sqr, _mm_sub_ps(c_sqr, _mm_mul_ps(tca, tca)));
+                let thc = _mm_sqrt_ps(_mm_max_ps(thc_sqr, zero));
+
+                let t0 = _mm_sub_ps(tca, thc);
+                let t = select(_mm_cmplt_ps(t0, zero), _mm_add_ps(tca, thc), t0);
+
+                let hit = _mm_and_ps(
+                    _mm_cmpge_ps(thc_sqr, zero),
+                    _mm_and_ps(_mm_cmpge_ps(t, zero), _mm_cmplt_ps(t, best_t)),
+                );
+                best_t = select(hit, t, best_t);
+                best_index = select(hit, _mm_set1_ps(index as f32), best_index);
+            }
+
+            let (mut t, mut indices) = ([0f32; 4], [0f32; 4]);
+            _mm_storeu_ps(t.as_mut_ptr(), best_t);
+            _mm_storeu_ps(indices.as_mut_ptr(), best_index);
+
+            nearest.extend(unpack(&t, &indices, rays.len()));
+        }
+
+        nearest
+    }
+
+    #[target_feature(enable = "avx")]
+    pub unsafe fn nearest_packet_avx(
+        spheres: &SphereSet,
+        rays: &[Ray],
+    ) -> Vec<Option<(usize, f32)>> {
+        let zero = _mm256_setzero_ps();
+        let mut nearest = Vec::with_capacity(rays.len());
+
+        for rays in rays.chunks(8) {
+            let origin_x = _mm256_loadu_ps(gather(rays, 0., |ray| ray.origin.x).as_ptr());
+            let origin_y = _mm256_loadu_ps(gather(rays, 0., |ray| ray.origin.y).as_ptr());
+            let origin_z = _mm256_loadu_ps(gather(rays, 0., |ray| ray.origin.z).as_ptr());
+            let direction_x = _mm256_loadu_ps(gather(rays, 0., |ray| ray.direction.x).as_ptr());
+            let direction_y = _mm256_loadu_ps(gather(rays, 0., |ray| ray.direction.y).as_ptr());
+            let direction_z = _mm256_loadu_ps(gather(rays, 0., |ray| ray.direction.z).as_ptr());
+
+            let mut best_t = _mm256_loadu_ps(gather(rays, 0., |ray| ray.t).as_ptr());
+            let mut best_index = _mm256_set1_ps(-1.);
+
+            for index in 0..spheres.len() {
+                let cx = _mm256_sub_ps(_mm256_set1_ps(spheres.centers_x[index]), origin_x);
+                let cy = _mm256_sub_ps(_mm256_set1_ps(spheres.centers_y[index]), origin_y);
+                let cz = _mm256_sub_ps(_mm256_set1_ps(spheres.centers_z[index]), origin_z);
+                let radius_sqr = _mm256_set1_ps(spheres.radii_sqr[index]);
+
+                let tca = _mm256_add_ps(
+                    _mm256_add_ps(
+                        _mm256_mul_ps(cx, direction_x),
+                        _mm256_mul_ps(cy, direction_y),
+                    ),
+                    _mm256_mul_ps(cz, direction_z),
+                );
+                let c_sqr = _mm256_add_ps(
+                    _mm256_add_ps(_mm256_mul_ps(cx, cx), _mm256_mul_ps(cy, cy)),
+                    _mm256_mul_ps(cz, cz),
+                );
+                let thc_sqr =
+                    _mm256_sub_ps(radius_sqr, _mm256_sub_ps(c_sqr, _mm256_mul_ps(tca, tca)));
+                let thc = _mm256_sqrt_ps(_mm256_max_ps(thc_sqr, zero));
+
+                let t0 = _mm256_sub_ps(tca, thc);
+                let t = _mm256_blendv_ps(
+                    t0,
+                    _mm256_add_ps(tca, thc),
+                    _mm256_cmp_ps(t0, zero, _CMP_LT_OQ),
+                );
+
+                let hit = _mm256_and_ps(
+                    _mm256_cmp_ps(thc_sqr, zero, _CMP_GE_OQ),
+                    _mm256_and_ps(
+                        _mm256_cmp_ps(t, zero, _CMP_GE_OQ),
+                        _mm256_cmp_ps(t, best_t, _CMP_LT_OQ),
+                    ),
+                );
+                best_t = _mm256_blendv_ps(best_t, t, hit);
+                best_index = _mm256_blendv_ps(best_index, _mm256_set1_ps(index as f32), hit);
+            }
+
+            let (mut t, mut indices) = ([0f32; 8], [0f32; 8]);
+            _mm256_storeu_ps(t.as_mut_ptr(), best_t);
+            _mm256_storeu_ps(indices.as_mut_ptr(), best_index);
+
+            nearest.extend(unpack(&t, &indices, rays.len()));
+        }
+
+        nearest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::Material;
+    use crate::geometry::object::Object;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::sphere_set::SphereSet;
+    use crate::geometry::vec3::Vec3;
+    use crate::utils::rgb::RGB;
+
+    use std::time::Instant;
+
+    fn material(shade: u8) -> Box<dyn Material> {
+        Box::new(Phong {
+            color: RGB::new(shade, shade, shade),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        })
+    }
+
+    // Deterministic pseudo-random grid of spheres in front of the origin.
+    fn sphere_field(count: usize) -> Vec<Sphere> {
+        (0..count)
+            .map(|i| {
+                let hash = (i as u32).wrapping_mul(2_654_435_761);
+                let jitter = (hash >> 16) as Float / 65_536.;
+                let (x, y) = ((i % 64) as Float - 32., ((i / 64) % 64) as Float - 32.);
+
+                Sphere::new(
+                    Vec3::new(
+                        x + jitter,
+                        y - jitter,
+                        -20. - (i / 4096) as Float - jitter * 10.,
+                    ),
+                    0.3 + jitter * 0.4,
+                    material((i % 256) as u8),
+                )
+            })
+            .collect()
+    }
+
+    fn rays(count: usize) -> Vec<Ray> {
+        (0..count)
+            .map(|i| {
+                let (x, y) = ((i % 100) as Float / 50. - 1., (i / 100) as Float / 50. - 1.);
+                let origin = if i % 7 == 0 {
+                    // Some rays start inside a sphere, as refracted rays do.
+                    Vec3::new(-32., -32., -20.)
+                } else {
+                    Vec3::zero()
+                };
+
+                Ray::new(origin, Vec3::new(x, y, -1.).normalize(), Float::MAX)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_scalar_spheres() {
+        let spheres = sphere_field(300);
+        let mut set = SphereSet::new();
+        for sphere in sphere_field(300) {
+            set.push(sphere);
+        }
+
+        for ray in rays(10_000) {
+            let expected = spheres.iter().enumerate().fold(
+                (None, Float::MAX),
+                |(nearest, t_max), (index, sphere)| {
+                    let mut probe = Ray::new(ray.origin, ray.direction, t_max);
+                    if sphere.ray_intersect(&mut probe) {
+                        (Some(index), probe.t)
+                    } else {
+                        (nearest, t_max)
+                    }
+                },
+            );
+
+            let mut set_ray = Ray::new(ray.origin, ray.direction, Float::MAX);
+            let hit = set.intersect(&mut set_ray);
+
+            assert_eq!(hit.as_ref().map(|hit| hit.primitive), expected.0);
+            if let Some(hit) = hit {
+                assert_eq!(set_ray.t, expected.1);
+                assert_eq!(set.material(&hit).color().r, (hit.primitive % 256) as u8);
+            }
+            assert_eq!(set.nearest_scalar(&ray), set.nearest(&ray));
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+            assert_eq!(set.nearest_scalar(&ray), unsafe {
+                super::x86::nearest_sse(&set, &ray)
+            });
+        }
+    }
+
+    #[test]
+    fn packets_match_single_rays() {
+        let mut set = SphereSet::new();
+        for sphere in sphere_field(300) {
+            set.push(sphere);
+        }
+        // An odd count leaves a partially filled packet at the end.
+        let rays = rays(1_003);
+        let expected: Vec<_> = rays.iter().map(|ray| set.nearest_scalar(ray)).collect();
+
+        assert_eq!(set.nearest_packet(&rays), expected);
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+        assert_eq!(unsafe { super::x86::nearest_packet_sse(&set, &rays) }, expected);
+
+        let mut packet = rays.clone();
+        let hits = set.intersect_packet(&mut packet);
+        for ((ray, hit), expected) in packet.iter().zip(hits).zip(expected) {
+            assert_eq!(hit.map(|hit| hit.primitive), expected.map(|(index, _)| index));
+            if let Some((_, t)) = expected {
+                assert_eq!(ray.t, t);
+            }
+        }
+    }
+
+    #[test]
+    fn padding_is_never_hit() {
+        // Rays from far away aimed at the origin, where the padding spheres sit.
+        let rays: Vec<_> = (0..37)
+            .map(|i| {
+                let i = i as Float;
+                let origin = Vec3::new(1043.8 - i * 57.1, -518.6 + i * 31.7, 2010.2 - i * 83.9);
+
+                Ray::new(origin, (Vec3::zero() - origin).normalize(), Float::MAX)
+            })
+            .collect();
+
+        // Every count leaves a partially filled last chunk.
+        for count in &[1, 3, 5, 11] {
+            let mut set = SphereSet::new();
+            for sphere in sphere_field(*count) {
+                set.push(sphere);
+            }
+            let expected: Vec<_> = rays.iter().map(|ray| set.nearest_scalar(ray)).collect();
+
+            for (ray, expected) in rays.iter().zip(&expected) {
+                assert_eq!(set.nearest(ray), *expected);
+                #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+                assert_eq!(unsafe { super::x86::nearest_sse(&set, ray) }, *expected);
+            }
+            assert_eq!(set.nearest_packet(&rays), expected);
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "f64")))]
+            assert_eq!(unsafe { super::x86::nearest_packet_sse(&set, &rays) }, expected);
+        }
+    }
+
+    // cargo test --release sphere_set -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let mut set = SphereSet::new();
+        for sphere in sphere_field(8192) {
+            set.push(sphere);
+        }
+        let rays = rays(10_000);
+
+        let start = Instant::now();
+        let scalar_hits = rays
+            .iter()
+            .filter(|ray| set.nearest_scalar(ray).is_some())
+            .count();
+        let scalar = start.elapsed();
+
+        let start = Instant::now();
+        let simd_hits = rays.iter().filter(|ray| set.nearest(ray).is_some()).count();
+        let simd = start.elapsed();
+
+        assert_eq!(scalar_hits, simd_hits);
+        println!(
+            "{} spheres x {} rays: scalar {:?}, simd {:?}, speedup {:.1}x",
+            set.len(),
+            rays.len(),
+            scalar,
+            simd,
+            scalar.as_secs_f64() / simd.as_secs_f64()
+        );
+    }
+}
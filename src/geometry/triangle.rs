@@ -0,0 +1,63 @@
+use super::Float;
+use super::vec3::Vec3;
+
+const EPSILON: Float = 1e-7;
+
+// Möller–Trumbore, returns the distance and the barycentric coordinates of `v1` and `v2`.
+pub fn ray_intersect(
+    origin: &Vec3,
+    direction: &Vec3,
+    v0: &Vec3,
+    v1: &Vec3,
+    v2: &Vec3,
+) -> Option<(Float, Float, Float)> {
+    let edge1 = *v1 - *v0;
+    let edge2 = *v2 - *v0;
+    let p = direction.cross(&edge2);
+    let det = edge1.dot_product(&p);
+
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let inv_det = 1. / det;
+    let s = *origin - *v0;
+    let u = s.dot_product(&p) * inv_det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = direction.dot_product(&q) * inv_det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = edge2.dot_product(&q) * inv_det;
+    if t < 0. {
+        return None;
+    }
+
+    Some((t, u, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::triangle;
+    use crate::geometry::vec3::Vec3;
+
+    #[test]
+    fn ray_intersect() {
+        let v0 = Vec3::new(-1., -1., -5.);
+        let v1 = Vec3::new(1., -1., -5.);
+        let v2 = Vec3::new(0., 1., -5.);
+        let orig = Vec3::new(0., 0., 0.);
+
+        let (t, _, _) =
+            triangle::ray_intersect(&orig, &Vec3::new(0., 0., -1.), &v0, &v1, &v2).unwrap();
+        assert_eq!(t, 5.);
+
+        let miss = triangle::ray_intersect(&orig, &Vec3::new(0., 0., 1.), &v0, &v1, &v2);
+        assert!(miss.is_none());
+    }
+}
@@ -1,79 +1,236 @@
+use super::Float;
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Vec3 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Vec3 {
-    pub fn new(x: f32, y: f32, z: f32) -> Self {
+    pub const fn new(x: Float, y: Float, z: Float) -> Self {
         Self { x, y, z }
     }
 
-    pub fn dot_product(&self, vec: &Vec3) -> f32 {
+    pub const fn zero() -> Self {
+        Self::new(0., 0., 0.)
+    }
+
+    pub fn dot_product(&self, vec: &Vec3) -> Float {
         self.x * vec.x + self.y * vec.y  + self.z * vec.z
     }
 
-    pub fn scale(&self, factor: f32) -> Self {
+    pub fn cross(&self, vec: &Vec3) -> Self {
         Self {
-            x: self.x * factor,
-            y: self.y * factor,
-            z: self.z * factor,
+            x: self.y * vec.z - self.z * vec.y,
+            y: self.z * vec.x - self.x * vec.z,
+            z: self.x * vec.y - self.y * vec.x,
         }
     }
 
-    pub fn divide(&self, factor: f32) -> Self {
-        Self {
-            x: self.x / factor,
-            y: self.y / factor,
-            z: self.z / factor,
-        }
+    pub fn length(&self) -> Float {
+        (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
     }
 
-    pub fn minus(&self, vec: &Vec3) -> Self {
-        Self {
-            x: self.x - vec.x,
-            y: self.y - vec.y,
-            z: self.z - vec.z,
-        }
+    pub fn normalize(&self) -> Self {
+        *self / self.length()
     }
 
-    pub fn plus(&self, vec: &Vec3) -> Self {
-        Self {
-            x: self.x + vec.x,
-            y: self.y + vec.y,
-            z: self.z + vec.z,
+    pub fn min(&self, vec: &Vec3) -> Self {
+        Self::new(self.x.min(vec.x), self.y.min(vec.y), self.z.min(vec.z))
+    }
+
+    pub fn max(&self, vec: &Vec3) -> Self {
+        Self::new(self.x.max(vec.x), self.y.max(vec.y), self.z.max(vec.z))
+    }
+
+    // Two unit vectors perpendicular to this unit vector and to each other, with their cross
+    // product along it. See Duff et al., "Building an Orthonormal Basis, Revisited".
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let sign = Float::copysign(1., self.z);
+        let a = -1. / (sign + self.z);
+        let b = self.x * self.y * a;
+
+        (
+            Self::new(1. + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Self::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
+    pub fn lerp(&self, vec: &Vec3, t: Float) -> Self {
+        *self + (*vec - *self) * t
+    }
+
+    // Mirrors the incoming direction around the normal.
+    pub fn reflect(&self, normal: &Vec3) -> Self {
+        *self - *normal * (2. * self.dot_product(normal))
+    }
+
+    // Snell's law for a ray going from `eta_i` into `eta_t`. The normal may face either side,
+    // total internal reflection yields an arbitrary direction.
+    pub fn refract(&self, normal: &Vec3, eta_t: Float, eta_i: Float) -> Self {
+        let cosi = -self.dot_product(normal).clamp(-1., 1.);
+        if cosi < 0. {
+            return self.refract(&-*normal, eta_i, eta_t);
+        };
+
+        let eta = eta_i / eta_t;
+        let k = 1. - eta.powi(2) * (1. - cosi.powi(2));
+
+        if k < 0. {
+            Vec3::new(1., 0., 0.)
+        } else {
+            *self * eta + *normal * (eta * cosi - k.sqrt())
         }
     }
+}
 
-    pub fn length(&self) -> f32 {
-        (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
+impl Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, vec: Self) -> Self {
+        Self::new(self.x + vec.x, self.y + vec.y, self.z + vec.z)
     }
+}
 
-    pub fn normalize(&self) -> Self {
-        self.divide(self.length())
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, vec: Self) {
+        *self = *self + vec;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, vec: Self) -> Self {
+        Self::new(self.x - vec.x, self.y - vec.y, self.z - vec.z)
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, vec: Self) {
+        *self = *self - vec;
+    }
+}
+
+impl Mul<Float> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, factor: Float) -> Self {
+        Self::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Mul<Vec3> for Float {
+    type Output = Vec3;
+
+    fn mul(self, vec: Vec3) -> Vec3 {
+        vec * self
+    }
+}
+
+// Component-wise.
+impl Mul for Vec3 {
+    type Output = Self;
+
+    fn mul(self, vec: Self) -> Self {
+        Self::new(self.x * vec.x, self.y * vec.y, self.z * vec.z)
+    }
+}
+
+impl Div<Float> for Vec3 {
+    type Output = Self;
+
+    fn div(self, factor: Float) -> Self {
+        Self::new(self.x / factor, self.y / factor, self.z / factor)
     }
+}
+
+// Component-wise.
+impl Div for Vec3 {
+    type Output = Self;
+
+    fn div(self, vec: Self) -> Self {
+        Self::new(self.x / vec.x, self.y / vec.y, self.z / vec.z)
+    }
+}
 
-    pub fn scale_by_matrix(&self, matrix: [[f32; 3]; 3]) -> Self {
-        let mut result = [0., 0., 0.];
-        let current = [self.x, self.y, self.z];
+impl Neg for Vec3 {
+    type Output = Self;
 
-        for i in 0..3 {
-            for j in 0..3 {
-                result[i] += current[j] * matrix[i][j];
-            }
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = Float;
+
+    fn index(&self, axis: usize) -> &Float {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 axis out of range: {}", axis),
         }
+    }
+}
 
-        Self { x: result[0], y: result[1], z: result[2] }
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, axis: usize) -> &mut Float {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vec3 axis out of range: {}", axis),
+        }
+    }
+}
+
+#[cfg(test)]
+pub trait ApproxEq: std::fmt::Debug {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool;
+}
+
+#[cfg(test)]
+impl ApproxEq for Float {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        (self - other).abs() <= epsilon
     }
+}
 
-    pub fn clone(&self) -> Self {
-        Self::new(self.x, self.y, self.z)
+#[cfg(test)]
+impl ApproxEq for Vec3 {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        (0..3).all(|axis| self[axis].approx_eq(&other[axis], epsilon))
     }
 }
 
+// Works for anything implementing `ApproxEq`, with an optional tolerance (1e-5 by default).
+#[cfg(test)]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr) => {
+        assert_approx_eq!($left, $right, 1e-5)
+    };
+    ($left:expr, $right:expr, $epsilon:expr) => {{
+        let (left, right) = (&$left, &$right);
+        assert!(
+            crate::geometry::vec3::ApproxEq::approx_eq(left, right, $epsilon),
+            "assertion failed: `left ≈ right`\n  left: `{:?}`\n right: `{:?}`",
+            left,
+            right
+        );
+    }};
+}
+
+#[cfg(test)]
+pub(crate) use assert_approx_eq;
+
 #[cfg(test)]
 mod tests {
-    use crate::geometry::vec3::{Vec3};
+    use crate::geometry::Float;
+    use crate::geometry::vec3::Vec3;
 
     #[test]
     fn length() {
@@ -101,7 +258,7 @@ mod tests {
     fn plus() {
         let vec1 = Vec3::new(1., 1., 1.);
         let vec2 = Vec3::new(1., 2., 3.);
-        let result = vec1.plus(&vec2);
+        let result = vec1 + vec2;
         
         assert_eq!(result.x, 2.0);
         assert_eq!(result.y, 3.0);
@@ -112,7 +269,7 @@ mod tests {
     fn minus() {
         let vec1 = Vec3::new(1., 1., 1.);
         let vec2 = Vec3::new(1., 2., 3.);
-        let result = vec1.minus(&vec2);
+        let result = vec1 - vec2;
         
         assert_eq!(result.x, 0.0);
         assert_eq!(result.y, -1.0);
@@ -131,17 +288,18 @@ mod tests {
     #[test]
     fn scale() {
         let vec = Vec3::new(3., 2., 4.);
-        let new_vec = vec.scale(2.);
+        let new_vec = vec * 2.;
 
         assert_eq!(new_vec.x, 6.);
         assert_eq!(new_vec.y, 4.);
         assert_eq!(new_vec.z, 8.);
+        assert_eq!(2. * vec, new_vec);
     }
 
      #[test]
     fn divide() {
         let vec = Vec3::new(3., 2., 4.);
-        let new_vec = vec.divide(2.);
+        let new_vec = vec / 2.;
 
         assert_eq!(new_vec.x, 1.5);
         assert_eq!(new_vec.y, 1.);
@@ -149,17 +307,58 @@ mod tests {
     }
 
     #[test]
-    fn scale_by_matrix() {
-        let vec = Vec3::new(2., 3., 4.);
-        let matrix = [
-            [1., 2., 3.],
-            [3., 2., 1.],
-            [4., 1., 3.],
-        ];
-        let result = vec.scale_by_matrix(matrix);
-
-        assert_eq!(result.x, 20.);
-        assert_eq!(result.y, 16.);
-        assert_eq!(result.z, 23.);
-    }
-}
\ No newline at end of file
+    fn component_wise() {
+        let vec1 = Vec3::new(1., 6., 3.);
+        let vec2 = Vec3::new(2., 3., -4.);
+
+        assert_eq!(vec1 * vec2, Vec3::new(2., 18., -12.));
+        assert_eq!(vec1 / vec2, Vec3::new(0.5, 2., -0.75));
+        assert_eq!(vec1.min(&vec2), Vec3::new(1., 3., -4.));
+        assert_eq!(vec1.max(&vec2), Vec3::new(2., 6., 3.));
+        assert_eq!(-vec1, Vec3::new(-1., -6., -3.));
+        assert_eq!(vec1[1], 6.);
+    }
+
+    #[test]
+    fn cross() {
+        let x = Vec3::new(1., 0., 0.);
+        let y = Vec3::new(0., 1., 0.);
+
+        assert_eq!(x.cross(&y), Vec3::new(0., 0., 1.));
+        assert_eq!(y.cross(&x), Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn lerp() {
+        let vec1 = Vec3::new(0., 2., 4.);
+        let vec2 = Vec3::new(4., 2., 0.);
+
+        assert_eq!(vec1.lerp(&vec2, 0.25), Vec3::new(1., 2., 3.));
+    }
+
+    #[test]
+    fn reflect() {
+        let direction = Vec3::new(1., -1., 0.);
+        let normal = Vec3::new(0., 1., 0.);
+
+        assert_eq!(direction.reflect(&normal), Vec3::new(1., 1., 0.));
+    }
+
+    #[test]
+    fn refract() {
+        let normal = Vec3::new(0., 1., 0.);
+        let direction = Vec3::new(1., -1., 0.).normalize();
+
+        // Passing straight through a surface with equal indices keeps the direction.
+        assert_approx_eq!(direction.refract(&normal, 1., 1.), direction);
+
+        // Snell's law: sin(theta_t) = sin(theta_i) / 1.5 when entering glass.
+        let refracted = direction.refract(&normal, 1.5, 1.);
+        assert_approx_eq!(refracted.x, Float::sqrt(0.5) / 1.5);
+        assert_approx_eq!(refracted.length(), 1.);
+
+        // Leaving glass at a steep angle reflects internally.
+        let exiting = Vec3::new(1., 0.2, 0.).normalize();
+        assert_eq!(exiting.refract(&normal, 1.5, 1.), Vec3::new(1., 0., 0.));
+    }
+}
@@ -0,0 +1,587 @@
+use crate::geometry::material::cutout::Cutout;
+use crate::geometry::material::emissive::Emissive;
+use crate::geometry::material::pbr::Pbr;
+use crate::geometry::material::relief::{Bumped, Relief};
+use crate::geometry::material::texture::{ImageTexture, Texture, Wrap};
+use crate::geometry::material::Material;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::object::Object;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::loaders::materials::MaterialLibrary;
+use crate::math::mat3::Mat3;
+use crate::math::mat4::Mat4;
+use crate::math::quaternion::Quaternion;
+use crate::scene::camera::Camera;
+use crate::scene::light::{Attenuation, Emitters, Light};
+use crate::scene::{Canvas, Scene, SceneOptions};
+use crate::utils::rgb::RGB;
+
+use gltf::camera::Projection;
+use gltf::image::Format;
+use gltf::khr_lights_punctual::Kind;
+use gltf::material::AlphaMode;
+use gltf::mesh::Mode;
+use gltf::scene::Transform;
+use gltf::texture::WrappingMode;
+use gltf::{buffer, image, Document, Node};
+use std::sync::Arc;
+
+const DEFAULT_IOR: Float = 1.5;
+const DEFAULT_LIGHT_INTENSITY: Float = 1.5;
+
+// A glTF texture, with its alpha channel apart for materials cut out by it.
+struct ImportedTexture {
+    color: Arc<dyn Texture>,
+    alpha: Option<Arc<dyn Texture>>,
+}
+
+struct Imported {
+    objects: Vec<Box<dyn Object>>,
+    lights: Vec<Light>,
+    camera: Option<(Camera, Float)>,
+}
+
+// Loads a .gltf or .glb file, keeping the canvas size and render options from the caller.
+// Materials named like one of `materials` are replaced by it.
+pub fn load_scene(
+    path: &str,
+    canvas: Canvas,
+    options: SceneOptions,
+    materials: &MaterialLibrary,
+) -> Result<Scene, String> {
+    let (document, buffers, images) =
+        gltf::import(path).map_err(|e| format!("Can't load {}: {}", path, e))?;
+
+    build_scene(&document, &buffers, &images, canvas, options, materials)
+        .map_err(|e| format!("Can't load {}: {}", path, e))
+}
+
+// Same as `load_scene` for a file already in memory, whose buffers and images are embedded.
+pub fn parse_scene(
+    bytes: &[u8],
+    canvas: Canvas,
+    options: SceneOptions,
+    materials: &MaterialLibrary,
+) -> Result<Scene, String> {
+    let (document, buffers, images) = gltf::import_slice(bytes).map_err(|e| e.to_string())?;
+
+    build_scene(&document, &buffers, &images, canvas, options, materials)
+}
+
+fn build_scene(
+    document: &Document,
+    buffers: &[buffer::Data],
+    images: &[image::Data],
+    canvas: Canvas,
+    options: SceneOptions,
+    materials: &MaterialLibrary,
+) -> Result<Scene, String> {
+    let gltf_scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or("file contains no scenes")?;
+    let textures = document
+        .textures()
+        .map(|texture| {
+            let (color, alpha) = convert_texture(&texture, images)?;
+            Ok(ImportedTexture {
+                color: Arc::new(color),
+                alpha: alpha.map(|alpha| Arc::new(alpha) as Arc<dyn Texture>),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut imported = Imported {
+        objects: Vec::new(),
+        lights: Vec::new(),
+        camera: None,
+    };
+
+    for node in gltf_scene.nodes() {
+        let identity = Mat4::identity();
+        import_node(&node, &identity, buffers, &textures, materials, &mut imported)?;
+    }
+
+    let mut canvas = canvas;
+    let camera = match imported.camera {
+        Some((camera, fov)) => {
+            canvas.fov = fov;
+            camera
+        }
+        None => Camera::new(Vec3::new(0., 0., 0.)),
+    };
+
+    // Without any light the scene renders black, so fall back to a light at the camera.
+    let emitters = Emitters::new(&imported.objects);
+    if imported.lights.is_empty() && emitters.is_empty() {
+        imported
+            .lights
+            .push(Light::new(camera.position, DEFAULT_LIGHT_INTENSITY));
+    }
+
+    Ok(Scene {
+        objects: imported.objects,
+        lights: imported.lights,
+        emitters,
+        camera,
+        canvas,
+        options,
+    })
+}
+
+fn import_node(
+    node: &Node,
+    parent: &Mat4,
+    buffers: &[buffer::Data],
+    textures: &[ImportedTexture],
+    materials: &MaterialLibrary,
+    imported: &mut Imported,
+) -> Result<(), String> {
+    let world = *parent * local_transform(&node.transform());
+    let position = world.transform_point(&Vec3::new(0., 0., 0.));
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                continue;
+            }
+
+            let reader =
+                primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+            let vertices: Vec<Vec3> = reader
+                .read_positions()
+                .ok_or_else(|| format!("mesh {} has a primitive without positions", mesh.index()))?
+                .map(|[x, y, z]| vector(x, y, z))
+                .collect();
+            let normals = reader.read_normals().map(|normals| {
+                normals
+                    .map(|[x, y, z]| vector(x, y, z))
+                    .collect::<Vec<Vec3>>()
+            });
+            let base_color = primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_factor();
+            let colors = reader.read_colors(0).map(|colors| {
+                colors
+                    .into_rgba_f32()
+                    .map(|[r, g, b, _]| {
+                        to_rgb(&[r * base_color[0], g * base_color[1], b * base_color[2]])
+                    })
+                    .collect::<Vec<RGB>>()
+            });
+            let uvs = reader.read_tex_coords(0).map(|uvs| {
+                uvs.into_f32()
+                    .map(|[u, v]| [u as Float, v as Float])
+                    .collect::<Vec<[Float; 2]>>()
+            });
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            if let Some(&index) = indices
+                .iter()
+                .find(|&&index| index as usize >= vertices.len())
+            {
+                return Err(format!(
+                    "mesh {} references vertex {} but only {} vertices exist",
+                    mesh.index(),
+                    index,
+                    vertices.len()
+                ));
+            }
+
+            let triangles = indices
+                .chunks_exact(3)
+                .map(|chunk| [chunk[0] as usize, chunk[1] as usize, chunk[2] as usize])
+                .collect();
+            let material = convert_material(&primitive.material(), textures, materials)?;
+
+            let mut mesh = Mesh::new(vertices, normals, colors, triangles, material);
+            if let Some(uvs) = uvs {
+                mesh = mesh.with_uvs(uvs);
+            }
+
+            imported.objects.push(Box::new(mesh.transformed(&world)));
+        }
+    }
+
+    if let Some(light) = node.light() {
+        let direction = world.transform_direction(&Vec3::new(0., 0., -1.));
+        // Intensity is taken as is: the renderer has no physical light units yet. Directional
+        // lights are as sharp as glTF has them. Lights with a range fade out smoothly before
+        // it, as glTF suggests.
+        let intensity = light.intensity() as Float;
+        let attenuation = match light.range() {
+            Some(range) => {
+                let range = range as Float;
+                Attenuation::Custom(Box::new(move |distance: Float| {
+                    (1. - (distance / range).powi(4)).clamp(0., 1.) / (distance * distance)
+                }))
+            }
+            None => Attenuation::InverseSquare,
+        };
+        let imported_light = match light.kind() {
+            Kind::Directional => Light::directional(direction, 0., intensity),
+            Kind::Point => Light::new(position, intensity),
+            Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => Light::spot(
+                position,
+                direction,
+                inner_cone_angle as Float,
+                outer_cone_angle as Float,
+                intensity,
+            ),
+        };
+        imported.lights.push(
+            imported_light
+                .with_color(to_rgb(&light.color()))
+                .with_attenuation(attenuation),
+        );
+    }
+
+    if let Some(camera) = node.camera() {
+        if imported.camera.is_none() {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                let mut result = Camera::new(position);
+                result.orientation = orientation(&world);
+                imported.camera = Some((result, perspective.yfov() as Float));
+            }
+        }
+    }
+
+    for child in node.children() {
+        import_node(&child, &world, buffers, textures, materials, imported)?;
+    }
+
+    Ok(())
+}
+
+// Textures read from other coordinate sets than the first are left out.
+fn convert_material(
+    material: &gltf::Material,
+    textures: &[ImportedTexture],
+    materials: &MaterialLibrary,
+) -> Result<Box<dyn Material>, String> {
+    if let Some(name) = material.name().filter(|name| materials.contains(name)) {
+        return materials.get(name);
+    }
+
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let transmission = material
+        .transmission()
+        .map_or(0., |transmission| transmission.transmission_factor());
+    let texture = |tex_coord: u32, texture: gltf::Texture| match tex_coord {
+        0 => textures.get(texture.index()).map(|texture| texture.color.clone()),
+        _ => None,
+    };
+    let info = |info: Option<gltf::texture::Info>| {
+        info.and_then(|info| texture(info.tex_coord(), info.texture()))
+    };
+
+    let base: Box<dyn Material> = Box::new(Pbr {
+        base_color: to_rgb(&[r, g, b]),
+        metallic: pbr.metallic_factor() as Float,
+        roughness: pbr.roughness_factor() as Float,
+        ior: material.ior().map_or(DEFAULT_IOR, |ior| ior as Float),
+        transmission: transmission as Float,
+        base_color_texture: info(pbr.base_color_texture()),
+        metallic_roughness_texture: info(pbr.metallic_roughness_texture()),
+    });
+
+    let normal_map = material.normal_texture().and_then(|normal| {
+        Some(Relief::NormalMap {
+            texture: texture(normal.tex_coord(), normal.texture())?,
+            scale: normal.scale() as Float,
+        })
+    });
+    let bumped: Box<dyn Material> = match normal_map {
+        Some(relief) => Box::new(Bumped {
+            material: base,
+            relief,
+        }),
+        None => base,
+    };
+
+    let emissive = material.emissive_factor();
+    let glowing: Box<dyn Material> = if emissive == [0., 0., 0.] {
+        bumped
+    } else {
+        Box::new(Emissive {
+            material: bumped,
+            color: to_rgb(&emissive),
+            strength: material.emissive_strength().unwrap_or(1.) as Float,
+        })
+    };
+
+    // Blended materials are rendered opaque.
+    if material.alpha_mode() != AlphaMode::Mask {
+        return Ok(glowing);
+    }
+    let alpha = pbr.base_color_texture().and_then(|info| match info.tex_coord() {
+        0 => textures.get(info.texture().index())?.alpha.clone(),
+        _ => None,
+    });
+    Ok(Box::new(Cutout {
+        material: glowing,
+        opacity: pbr.base_color_factor()[3] as Float,
+        opacity_texture: alpha,
+        cutoff: material.alpha_cutoff().unwrap_or(0.5) as Float,
+    }))
+}
+
+// Texels are taken as display values like the rest of the renderer's colors. That's right for
+// base colors, which glTF stores in sRGB, and keeps data textures such as roughness linear.
+// Images with an alpha channel also give it as a gray texture.
+fn convert_texture(
+    texture: &gltf::Texture,
+    images: &[image::Data],
+) -> Result<(ImageTexture, Option<ImageTexture>), String> {
+    let image = images
+        .get(texture.source().index())
+        .ok_or_else(|| format!("texture {} has no image", texture.index()))?;
+    let channels = match image.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        _ => return Err(format!("texture {} isn't 8 bits per channel", texture.index())),
+    };
+
+    let texels = image
+        .pixels
+        .chunks_exact(channels)
+        .map(|pixel| {
+            // Gray images fill all three channels, two channel ones leave blue out.
+            let channel = |index: usize| match channels {
+                1 => pixel[0] as Float / 255.,
+                _ => pixel.get(index).map_or(0., |&value| value as Float / 255.),
+            };
+            Vec3::new(channel(0), channel(1), channel(2))
+        })
+        .collect();
+    let sampler = texture.sampler();
+    let wrap = (to_wrap(sampler.wrap_s()), to_wrap(sampler.wrap_t()));
+    let (width, height) = (image.width as usize, image.height as usize);
+
+    let alpha = match channels {
+        4 => {
+            let alphas = image
+                .pixels
+                .chunks_exact(4)
+                .map(|pixel| {
+                    let alpha = pixel[3] as Float / 255.;
+                    Vec3::new(alpha, alpha, alpha)
+                })
+                .collect();
+            Some(ImageTexture::new(width, height, alphas, wrap))
+        }
+        _ => None,
+    };
+
+    Ok((ImageTexture::new(width, height, texels, wrap), alpha))
+}
+
+fn to_wrap(mode: WrappingMode) -> Wrap {
+    match mode {
+        WrappingMode::Repeat => Wrap::Repeat,
+        WrappingMode::MirroredRepeat => Wrap::MirroredRepeat,
+        WrappingMode::ClampToEdge => Wrap::ClampToEdge,
+    }
+}
+
+// glTF colors are linear, the renderer works with display colors.
+fn to_rgb(linear: &[f32; 3]) -> RGB {
+    let channel = |value: f32| (value.clamp(0., 1.).powf(1. / 2.2) * 255.).round() as u8;
+
+    RGB::new(channel(linear[0]), channel(linear[1]), channel(linear[2]))
+}
+
+fn local_transform(transform: &Transform) -> Mat4 {
+    match transform {
+        Transform::Matrix { matrix } => {
+            let mut columns = [[0.; 4]; 4];
+            for (column, values) in columns.iter_mut().zip(matrix) {
+                for (value, &original) in column.iter_mut().zip(values) {
+                    *value = original as Float;
+                }
+            }
+
+            Mat4::from_columns(columns)
+        }
+        Transform::Decomposed {
+            translation: [x, y, z],
+            rotation: [rx, ry, rz, rw],
+            scale: [sx, sy, sz],
+        } => Mat4::from_translation_rotation_scale(
+            &vector(*x, *y, *z),
+            &Quaternion::new(*rx as Float, *ry as Float, *rz as Float, *rw as Float),
+            &vector(*sx, *sy, *sz),
+        ),
+    }
+}
+
+fn vector(x: f32, y: f32, z: f32) -> Vec3 {
+    Vec3::new(x as Float, y as Float, z as Float)
+}
+
+// The rotation part of a node transform, with its scale removed.
+fn orientation(transform: &Mat4) -> Quaternion {
+    let linear = transform.linear();
+
+    Quaternion::from_mat3(&Mat3::from_columns(
+        &linear.column(0).normalize(),
+        &linear.column(1).normalize(),
+        &linear.column(2).normalize(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::Surface;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::loaders::gltf;
+    use crate::loaders::materials::MaterialLibrary;
+    use crate::scene::light::{Attenuation, Source};
+    use crate::scene::{Canvas, Integrator, SceneOptions};
+    use crate::utils::rgb::RGB;
+
+    // One triangle with positions (0,0,0), (1,0,0), (0,1,0) in an embedded buffer, moved to
+    // z = -5, plus a camera lifted to y = 1 and a red point light.
+    const TRIANGLE_SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [{ "type": "point", "intensity": 2.5, "color": [1, 0, 0] }]
+            }
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [0, 1, 2] }],
+        "nodes": [
+            { "mesh": 0, "translation": [0, 0, -5] },
+            { "camera": 0, "translation": [0, 1, 0] },
+            { "translation": [0, 0, 5], "children": [3] },
+            { "translation": [1, 2, 3], "extensions": { "KHR_lights_punctual": { "light": 0 } } }
+        ],
+        "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.8, "znear": 0.1 } }],
+        "materials": [{
+            "name": "red_metal",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [1, 0, 0, 1],
+                "metallicFactor": 1,
+                "roughnessFactor": 0
+            }
+        }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+        "accessors": [{
+            "bufferView": 0,
+            "componentType": 5126,
+            "count": 3,
+            "type": "VEC3",
+            "min": [0, 0, 0],
+            "max": [1, 1, 0]
+        }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "buffers": [{
+            "byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+        }]
+    }"#;
+
+    fn canvas() -> Canvas {
+        Canvas {
+            width: 64,
+            height: 64,
+            fov: 1.,
+        }
+    }
+
+    fn options() -> SceneOptions {
+        SceneOptions {
+            reflections_limit: 1,
+            background_color: RGB::new(0, 0, 0),
+            samples_per_pixel: 1,
+            packet_tracing: true,
+            integrator: Integrator::Whitted,
+        }
+    }
+
+    #[test]
+    fn parse_scene() {
+        let materials = MaterialLibrary::builtin();
+        let scene = gltf::parse_scene(TRIANGLE_SCENE.as_bytes(), canvas(), options(), &materials);
+
+        let scene = scene.unwrap();
+
+        assert_eq!(scene.objects.len(), 1);
+
+        let mut ray = Ray::new(Vec3::new(0.25, 0.25, 0.), Vec3::new(0., 0., -1.), Float::MAX);
+        let hit = scene.objects[0].intersect(&mut ray).unwrap();
+        assert_eq!(ray.t, 5.);
+        // A smooth red metal mirrors in red.
+        let material = scene.objects[0].material(&hit);
+        let surface = Surface {
+            normal: hit.normal,
+            tangent: hit.tangent,
+            bitangent: hit.bitangent,
+            color: material.color().as_vector() / 255.,
+            uv: hit.uv,
+            footprint: 0.,
+            point: hit.point,
+            local_point: hit.local_point,
+            wavelength: None,
+        };
+        let lobes = material.specular(&surface, &Vec3::new(0., 0., 1.));
+        assert_eq!(material.color().r, 255);
+        assert_eq!(lobes.len(), 1);
+        assert_approx_eq!(lobes[0].weight, Vec3::new(1., 0., 0.));
+        assert_eq!(scene.camera.position.y, 1.);
+        assert_approx_eq!(scene.canvas.fov, 0.8);
+        assert_eq!(scene.lights.len(), 1);
+        match scene.lights[0].source {
+            Source::Point(position) => assert_eq!(position.z, 8.),
+            _ => panic!("expected a point light"),
+        }
+        assert_eq!(scene.lights[0].intensity, 2.5);
+        assert_eq!(scene.lights[0].color.as_array(), [255, 0, 0]);
+        assert!(matches!(
+            scene.lights[0].attenuation,
+            Attenuation::InverseSquare
+        ));
+    }
+
+    #[test]
+    fn library_materials_replace_named_ones() {
+        let library_path = std::env::temp_dir().join("raytracing_named.materials");
+        let library = "[red_metal]\ntype = lambertian\ncolor = 0 0 255\n";
+        std::fs::write(&library_path, library).unwrap();
+        let materials = MaterialLibrary::load(library_path.to_str().unwrap()).unwrap();
+        let scene = gltf::parse_scene(TRIANGLE_SCENE.as_bytes(), canvas(), options(), &materials);
+
+        let scene = scene.unwrap();
+        let mut ray = Ray::new(Vec3::new(0.25, 0.25, 0.), Vec3::new(0., 0., -1.), Float::MAX);
+        let hit = scene.objects[0].intersect(&mut ray).unwrap();
+        assert_eq!(scene.objects[0].material(&hit).color().as_array(), [0, 0, 255]);
+    }
+
+    #[test]
+    fn load_scene() {
+        let path = std::env::temp_dir().join("raytracing_triangle_scene.gltf");
+        std::fs::write(&path, TRIANGLE_SCENE).unwrap();
+        let materials = MaterialLibrary::builtin();
+
+        let scene = gltf::load_scene(path.to_str().unwrap(), canvas(), options(), &materials);
+
+        assert_eq!(scene.unwrap().objects.len(), 1);
+        let missing = gltf::load_scene("missing.gltf", canvas(), options(), &materials);
+        assert!(missing.err().unwrap().starts_with("Can't load missing.gltf"));
+    }
+}
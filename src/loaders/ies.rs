@@ -0,0 +1,168 @@
+use crate::geometry::Float;
+use crate::scene::light::Profile;
+
+use std::fs;
+
+// Numbers on the line after the tilt data: lamp count, lumens per lamp, candela multiplier,
+// vertical and horizontal angle counts, photometric type, units, the fixture's width, length and
+// height, ballast factor, a number kept for future use and input watts.
+const HEADER_LENGTH: usize = 13;
+const VERTICAL_COUNT: usize = 3;
+const HORIZONTAL_COUNT: usize = 4;
+const PHOTOMETRIC_TYPE: usize = 5;
+
+// Light profiles of real fixtures, in the IES LM-63 format their makers publish. Only type C
+// photometry is read, the one used for architectural lighting.
+pub fn load(path: &str) -> Result<Profile, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+
+    parse(&source).map_err(|e| format!("Can't load {}: {}", path, e))
+}
+
+pub fn parse(source: &str) -> Result<Profile, String> {
+    // Keywords describing the fixture come first, numbers follow the tilt line.
+    let mut lines = source.lines();
+    let tilt = lines
+        .by_ref()
+        .map(str::trim)
+        .find(|line| line.starts_with("TILT="))
+        .ok_or("file has no TILT line")?;
+    let mut numbers = lines
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|token| !token.is_empty());
+    let mut next = |what: &str| -> Result<Float, String> {
+        let token = numbers
+            .next()
+            .ok_or_else(|| format!("file ends before the {}", what))?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid {} '{}'", what, token))
+    };
+    // Counts can't be larger than the tokens left, each at least a character and a separator.
+    let room = source.len() / 2;
+
+    match tilt {
+        "TILT=NONE" => {}
+        // Lamp to fixture geometry, then pairs of tilt angles and factors, left out.
+        "TILT=INCLUDE" => {
+            next("lamp geometry")?;
+            let count = to_count(next("tilt angle count")?, "tilt angle count", room)?;
+            let pairs = count
+                .checked_mul(2)
+                .ok_or_else(|| format!("invalid tilt angle count {}", count))?;
+            for _ in 0..pairs {
+                next("tilt angles")?;
+            }
+        }
+        _ => {
+            return Err(format!(
+                "tilt in another file isn't supported, got '{}'",
+                tilt
+            ))
+        }
+    }
+
+    let header = (0..HEADER_LENGTH)
+        .map(|_| next("header"))
+        .collect::<Result<Vec<_>, String>>()?;
+    if header[PHOTOMETRIC_TYPE] != 1. {
+        return Err(format!(
+            "photometric type {} isn't supported, expected 1",
+            header[PHOTOMETRIC_TYPE]
+        ));
+    }
+    let vertical_count = to_count(header[VERTICAL_COUNT], "vertical angle count", room)?;
+    let horizontal_count = to_count(header[HORIZONTAL_COUNT], "horizontal angle count", room)?;
+    if vertical_count == 0 || horizontal_count == 0 {
+        return Err("profile has no angles".to_string());
+    }
+
+    let vertical = (0..vertical_count)
+        .map(|_| next("vertical angles"))
+        .collect::<Result<Vec<_>, String>>()?;
+    let horizontal = (0..horizontal_count)
+        .map(|_| next("horizontal angles"))
+        .collect::<Result<Vec<_>, String>>()?;
+    let values = (0..horizontal_count)
+        .map(|_| {
+            (0..vertical_count)
+                .map(|_| next("candela values"))
+                .collect::<Result<Vec<_>, String>>()
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Profile::new(vertical, horizontal, values))
+}
+
+// A whole number of things, no more than `room`.
+fn to_count(value: Float, what: &str, room: usize) -> Result<usize, String> {
+    if value >= 0. && value.fract() == 0. && value <= room as Float {
+        Ok(value as usize)
+    } else {
+        Err(format!("invalid {} {}", what, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::assert_approx_eq;
+    use crate::loaders::ies;
+
+    // A downlight brighter on its axis, with two alike halves that differ across them.
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002\n\
+        [MANUFAC] Example\n\
+        TILT=NONE\n\
+        1 1000 1 3 3 1 1 0.1 0.1 0\n\
+        1.0 1 20\n\
+        0 45 90\n\
+        0 90 180\n\
+        1000 500 0\n\
+        1000 300 0\n\
+        1000 100 0\n";
+
+    #[test]
+    fn downlight() {
+        let profile = ies::parse(DOWNLIGHT).unwrap();
+
+        assert_approx_eq!(profile.value(0., 0.), 1.);
+        assert_approx_eq!(profile.value(45., 0.), 0.5);
+        assert_approx_eq!(profile.value(22.5, 0.), 0.75);
+        assert_approx_eq!(profile.value(45., 45.), 0.4);
+        // Mirrored across the 0-180 plane.
+        assert_approx_eq!(profile.value(45., 270.), 0.3);
+        assert_approx_eq!(profile.value(45., -45.), 0.4);
+        // Nothing goes upwards.
+        assert_eq!(profile.value(120., 0.), 0.);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            ies::parse("IESNA:LM-63-2002\n1 2 3").err().unwrap(),
+            "file has no TILT line"
+        );
+        assert_eq!(
+            ies::parse(&DOWNLIGHT.replace("3 3 1 1", "3 3 2 1"))
+                .err()
+                .unwrap(),
+            "photometric type 2 isn't supported, expected 1"
+        );
+        assert_eq!(
+            ies::parse(&DOWNLIGHT[..DOWNLIGHT.len() - 8]).err().unwrap(),
+            "file ends before the candela values"
+        );
+        assert_eq!(
+            ies::parse(&DOWNLIGHT.replace("1 1000 1 3 3", "1 1000 1 1e12 3"))
+                .err()
+                .unwrap(),
+            "invalid vertical angle count 1000000000000"
+        );
+        assert!(ies::parse(&DOWNLIGHT.replace("1 1000 1 3 3", "1 1000 1 3 -3")).is_err());
+        for count in &["1e19", "-2", "1.5"] {
+            let tilted = DOWNLIGHT.replace("TILT=NONE", &format!("TILT=INCLUDE\n1 {}", count));
+            let error = ies::parse(&tilted).err().unwrap();
+            assert!(error.starts_with("invalid tilt angle count"), "{}", error);
+        }
+        assert!(ies::load("missing.ies").is_err());
+    }
+}
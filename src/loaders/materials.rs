@@ -0,0 +1,578 @@
+use crate::geometry::material::clearcoat::Clearcoat;
+use crate::geometry::material::conductor::Conductor;
+use crate::geometry::material::cutout::Cutout;
+use crate::geometry::material::dielectric::Dielectric;
+use crate::geometry::material::dispersion::{Dispersion, D_LINE};
+use crate::geometry::material::emissive::Emissive;
+use crate::geometry::material::lambertian::Lambertian;
+use crate::geometry::material::mirror::Mirror;
+use crate::geometry::material::pbr::Pbr;
+use crate::geometry::material::phong::Phong;
+use crate::geometry::material::procedural::{Pattern, Procedural, Space};
+use crate::geometry::material::relief::{Bumped, Relief};
+use crate::geometry::material::subsurface::{Medium, Translucent};
+use crate::geometry::material::texture::Texture;
+use crate::geometry::material::thin_film::ThinFilm;
+use crate::geometry::material::Material;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+const PRESETS: &str = include_str!("presets.materials");
+
+// Properties every type of material takes: a glow, a bump map, a clearcoat, a thin film and
+// holes.
+const COMMON_PROPERTIES: [&str; 16] = [
+    "type",
+    "emission",
+    "emission_strength",
+    "bump_pattern",
+    "bump_scale",
+    "bump_height",
+    "clearcoat_ior",
+    "clearcoat_roughness",
+    "clearcoat_tint",
+    "clearcoat_thickness",
+    "film_thickness",
+    "film_ior",
+    "opacity",
+    "opacity_pattern",
+    "opacity_scale",
+    "opacity_cutoff",
+];
+
+// Raw values by property name, checked when the material is built.
+type Definition = HashMap<String, String>;
+
+// Named materials, so that scenes can pick them by name and tweak them without recompiling.
+//
+// Library files are made of sections like
+//
+//     # Comment
+//     [name]
+//     base = copper
+//     roughness = 0.1
+//
+// `type` picks the shading model and `base` copies the properties of a material defined
+// earlier, the lines after it override them. A section for a name defined earlier, e.g. a
+// preset, only changes the properties it sets.
+pub struct MaterialLibrary {
+    definitions: HashMap<String, Definition>,
+}
+
+impl MaterialLibrary {
+    pub fn builtin() -> Self {
+        let mut library = Self {
+            definitions: HashMap::new(),
+        };
+        library
+            .parse(PRESETS)
+            .expect("Built-in materials must be valid");
+
+        library
+    }
+
+    // The presets, overridden and extended by the file at `path`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+        let mut library = Self::builtin();
+
+        library
+            .parse(&source)
+            .map_err(|e| format!("Can't load {}: {}", path, e))?;
+
+        Ok(library)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.definitions.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Box<dyn Material>, String> {
+        self.build(name, None)
+    }
+
+    // The material with its color multiplied by `texture`.
+    pub fn textured(
+        &self,
+        name: &str,
+        texture: Arc<dyn Texture>,
+    ) -> Result<Box<dyn Material>, String> {
+        self.build(name, Some(texture))
+    }
+
+    fn parse(&mut self, source: &str) -> Result<(), String> {
+        let mut section: Option<String> = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(error(format!("invalid material name '{}'", name)));
+                }
+
+                self.definitions.entry(name.to_string()).or_default();
+                section = Some(name.to_string());
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(position) => (line[..position].trim(), line[position + 1..].trim()),
+                None => {
+                    return Err(error(format!(
+                        "expected 'property = value', got '{}'",
+                        line
+                    )))
+                }
+            };
+            let name = section
+                .as_ref()
+                .ok_or_else(|| error(format!("'{}' is outside of any [material]", key)))?;
+
+            if key == "base" {
+                let base = self
+                    .definitions
+                    .get(value)
+                    .cloned()
+                    .ok_or_else(|| error(format!("unknown base material '{}'", value)))?;
+                self.definitions.get_mut(name).unwrap().extend(base);
+            } else {
+                self.definitions
+                    .get_mut(name)
+                    .unwrap()
+                    .insert(key.to_string(), value.to_string());
+            }
+        }
+
+        // Mistakes show up when the file is loaded rather than when the material is first used.
+        for name in self.definitions.keys() {
+            self.build(name, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn build(
+        &self,
+        name: &str,
+        texture: Option<Arc<dyn Texture>>,
+    ) -> Result<Box<dyn Material>, String> {
+        let definition = self
+            .definitions
+            .get(name)
+            .ok_or_else(|| format!("unknown material '{}'", name))?;
+        let values = Values { name, definition };
+
+        let kind = definition.get("type").map_or("", String::as_str);
+        let (material, properties): (Box<dyn Material>, &[&str]) = match kind {
+            "lambertian" => (
+                Box::new(Lambertian {
+                    color: values.color("color")?,
+                    color_texture: texture,
+                }),
+                &["color"],
+            ),
+            "mirror" => (
+                Box::new(Mirror {
+                    color: values.color("color")?,
+                    color_texture: texture,
+                }),
+                &["color"],
+            ),
+            "conductor" => (
+                Box::new(Conductor {
+                    color: values.color("color")?,
+                    roughness: values.number("roughness", 0.5)?,
+                    color_texture: texture,
+                    roughness_texture: None,
+                }),
+                &["color", "roughness"],
+            ),
+            "dielectric" => {
+                let ior = values.number("ior", 1.5)?;
+                let dispersion = values.dispersion(ior)?;
+                (
+                    Box::new(Dielectric {
+                        color: values.color("color")?,
+                        ior: dispersion.map_or(ior, |dispersion| dispersion.ior(D_LINE)),
+                        dispersion,
+                        color_texture: texture,
+                    }),
+                    &["color", "ior", "abbe", "cauchy", "sellmeier"],
+                )
+            }
+            "pbr" => (
+                Box::new(Pbr {
+                    base_color: values.color("color")?,
+                    metallic: values.number("metallic", 0.)?,
+                    roughness: values.number("roughness", 0.5)?,
+                    ior: values.number("ior", 1.5)?,
+                    transmission: values.number("transmission", 0.)?,
+                    base_color_texture: texture,
+                    metallic_roughness_texture: None,
+                }),
+                &["color", "metallic", "roughness", "ior", "transmission"],
+            ),
+            "phong" => {
+                let albedo = values.numbers("albedo", &[1., 0., 0., 0.])?;
+                (
+                    Box::new(Phong {
+                        color: values.color("color")?,
+                        albedo: (albedo[0], albedo[1], albedo[2], albedo[3]),
+                        specular_exponent: values.number("specular_exponent", 1.)?,
+                        refractive_index: values.number("refractive_index", 1.)?,
+                        color_texture: texture,
+                        specular_texture: None,
+                    }),
+                    &["color", "albedo", "specular_exponent", "refractive_index"],
+                )
+            }
+            // Its color comes from the medium, there's nothing for a texture to multiply.
+            "translucent" if texture.is_some() => {
+                return Err(format!("material '{}' can't be textured", name))
+            }
+            "translucent" => (
+                Box::new(Translucent {
+                    medium: Medium {
+                        scattering: values.coefficients("scattering", 1.)?,
+                        absorption: values.coefficients("absorption", 0.)?,
+                    },
+                    ior: values.number("ior", 1.4)?,
+                    roughness: values.number("roughness", 0.3)?,
+                }),
+                &["scattering", "absorption", "ior", "roughness"],
+            ),
+            "" => return Err(format!("material '{}' has no type", name)),
+            _ => {
+                return Err(format!(
+                    "material '{}' has unknown type '{}', expected lambertian, mirror, \
+                     conductor, dielectric, pbr, phong or translucent",
+                    name, kind
+                ))
+            }
+        };
+
+        for key in definition.keys() {
+            if !properties.contains(&key.as_str()) && !COMMON_PROPERTIES.contains(&key.as_str()) {
+                return Err(format!(
+                    "material '{}' of type {} has no property '{}'",
+                    name, kind, key
+                ));
+            }
+        }
+
+        let coated = definition.keys().any(|key| key.starts_with("clearcoat_"));
+        let material: Box<dyn Material> = if coated {
+            Box::new(Clearcoat {
+                base: material,
+                ior: values.number("clearcoat_ior", 1.5)?,
+                roughness: values.number("clearcoat_roughness", 0.)?,
+                tint: values.color("clearcoat_tint")?,
+                thickness: values.number("clearcoat_thickness", 1.)?,
+            })
+        } else {
+            material
+        };
+
+        let material = match definition.get("film_thickness") {
+            Some(_) => Box::new(ThinFilm {
+                material,
+                thickness: values.number("film_thickness", 0.)?,
+                ior: values.number("film_ior", 1.33)?,
+            }),
+            None => material,
+        };
+
+        let material = match definition.get("bump_pattern") {
+            Some(_) => Box::new(Bumped {
+                material,
+                relief: Relief::BumpMap {
+                    texture: Arc::new(Procedural {
+                        pattern: values.pattern("bump_pattern")?,
+                        colors: (RGB::new(0, 0, 0), RGB::new(255, 255, 255)),
+                        scale: values.number("bump_scale", 1.)?,
+                        space: Space::Object,
+                    }),
+                    height: values.number("bump_height", 0.05)?,
+                },
+            }),
+            None => material,
+        };
+
+        let material = match definition.get("emission") {
+            Some(_) => Box::new(Emissive {
+                material,
+                color: values.color("emission")?,
+                strength: values.number("emission_strength", 1.)?,
+            }),
+            None => material,
+        };
+
+        let cut = definition.keys().any(|key| key.starts_with("opacity"));
+        if !cut {
+            return Ok(material);
+        }
+        let opacity_texture = match definition.get("opacity_pattern") {
+            Some(_) => Some(Arc::new(Procedural {
+                pattern: values.pattern("opacity_pattern")?,
+                colors: (RGB::new(0, 0, 0), RGB::new(255, 255, 255)),
+                scale: values.number("opacity_scale", 1.)?,
+                space: Space::Object,
+            }) as Arc<dyn Texture>),
+            None => None,
+        };
+        Ok(Box::new(Cutout {
+            material,
+            opacity: values.number("opacity", 1.)?,
+            opacity_texture,
+            cutoff: values.number("opacity_cutoff", 0.5)?,
+        }))
+    }
+}
+
+// Property values of one material, parsed on demand.
+struct Values<'a> {
+    name: &'a str,
+    definition: &'a Definition,
+}
+
+impl<'a> Values<'a> {
+    fn invalid(&self, key: &str, expected: &str) -> String {
+        format!(
+            "material '{}' has invalid {} '{}', expected {}",
+            self.name, key, self.definition[key], expected
+        )
+    }
+
+    fn numbers(&self, key: &str, default: &[Float]) -> Result<Vec<Float>, String> {
+        let value = match self.definition.get(key) {
+            Some(value) => value,
+            None => return Ok(default.to_vec()),
+        };
+        let expected = format!("{} numbers", default.len());
+
+        let numbers = value
+            .split_whitespace()
+            .map(|number| number.parse::<Float>())
+            .collect::<Result<Vec<Float>, _>>()
+            .map_err(|_| self.invalid(key, &expected))?;
+        if numbers.len() != default.len() || numbers.iter().any(|number| !number.is_finite()) {
+            return Err(self.invalid(key, &expected));
+        }
+
+        Ok(numbers)
+    }
+
+    fn number(&self, key: &str, default: Float) -> Result<Float, String> {
+        self.numbers(key, &[default]).map(|numbers| numbers[0])
+    }
+
+    // White unless set.
+    fn color(&self, key: &str) -> Result<RGB, String> {
+        let channels = self.numbers(key, &[255., 255., 255.])?;
+        if channels
+            .iter()
+            .any(|&channel| !(0. ..=255.).contains(&channel))
+        {
+            return Err(self.invalid(key, "3 numbers from 0 to 255"));
+        }
+
+        Ok(RGB::new(
+            channels[0] as u8,
+            channels[1] as u8,
+            channels[2] as u8,
+        ))
+    }
+
+    // Red, green and blue values of a medium's coefficient.
+    fn coefficients(&self, key: &str, default: Float) -> Result<Vec3, String> {
+        let numbers = self.numbers(key, &[default; 3])?;
+        if numbers.iter().any(|&number| number < 0.) {
+            return Err(self.invalid(key, "3 numbers from 0 up"));
+        }
+
+        Ok(Vec3::new(numbers[0], numbers[1], numbers[2]))
+    }
+
+    // From the Abbe number, which goes with `ior`, or from Cauchy or Sellmeier coefficients.
+    fn dispersion(&self, ior: Float) -> Result<Option<Dispersion>, String> {
+        let given = ["abbe", "cauchy", "sellmeier"]
+            .iter()
+            .filter(|key| self.definition.contains_key(**key))
+            .count();
+        if given > 1 {
+            return Err(format!(
+                "material '{}' has more than one of abbe, cauchy and sellmeier",
+                self.name
+            ));
+        }
+
+        if self.definition.contains_key("abbe") {
+            let abbe = self.number("abbe", 0.)?;
+            if abbe <= 0. {
+                return Err(self.invalid("abbe", "a positive number"));
+            }
+            return Ok(Some(Dispersion::from_abbe(ior, abbe)));
+        }
+        if self.definition.contains_key("cauchy") {
+            let coefficients = self.numbers("cauchy", &[ior, 0.])?;
+            return Ok(Some(Dispersion::Cauchy {
+                a: coefficients[0],
+                b: coefficients[1],
+            }));
+        }
+        if self.definition.contains_key("sellmeier") {
+            let coefficients = self.numbers("sellmeier", &[0.; 6])?;
+            return Ok(Some(Dispersion::Sellmeier {
+                b: [coefficients[0], coefficients[1], coefficients[2]],
+                c: [coefficients[3], coefficients[4], coefficients[5]],
+            }));
+        }
+
+        Ok(None)
+    }
+
+    fn pattern(&self, key: &str) -> Result<Pattern, String> {
+        match self.definition[key].as_str() {
+            "checker" => Ok(Pattern::Checker),
+            "noise" => Ok(Pattern::Noise),
+            "turbulence" => Ok(Pattern::Turbulence),
+            "voronoi" => Ok(Pattern::Voronoi),
+            _ => Err(self.invalid(key, "checker, noise, turbulence or voronoi")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::texture::{ImageTexture, Wrap};
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::loaders::materials::MaterialLibrary;
+
+    use std::sync::Arc;
+
+    fn library(source: &str) -> Result<MaterialLibrary, String> {
+        let mut library = MaterialLibrary::builtin();
+        library.parse(source)?;
+
+        Ok(library)
+    }
+
+    #[test]
+    fn presets() {
+        let library = MaterialLibrary::builtin();
+
+        for name in &[
+            "mirror",
+            "glass",
+            "gold",
+            "chrome",
+            "water",
+            "diamond",
+            "red_plastic",
+        ] {
+            assert!(library.get(name).is_ok(), "{}", name);
+        }
+        assert_eq!(
+            library.get("copper").unwrap().color().as_array(),
+            [243, 162, 137]
+        );
+        assert!(library.get("hammered_copper").is_ok());
+        assert_approx_eq!(
+            library.get("light_bulb").unwrap().emission(),
+            Vec3::new(6., 214. / 255. * 6., 160. / 255. * 6.)
+        );
+        assert_eq!(
+            library.get("unobtainium").err().unwrap(),
+            "unknown material 'unobtainium'"
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let library = library(
+            "# Warmer and rougher.\n\
+             [copper]\n\
+             color = 250 150 120  # measured\n\
+             \n\
+             [pink_neon]\n\
+             base = red_plastic\n\
+             color = 255 120 200\n\
+             emission = 255 60 180\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            library.get("copper").unwrap().color().as_array(),
+            [250, 150, 120]
+        );
+        let neon = library.get("pink_neon").unwrap();
+        assert_eq!(neon.color().as_array(), [255, 120, 200]);
+        assert_approx_eq!(neon.emission(), Vec3::new(1., 60. / 255., 180. / 255.));
+        // The preset it's based on stays as it was.
+        assert_eq!(
+            library.get("red_plastic").unwrap().color().as_array(),
+            [200, 30, 30]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| library(source).err().unwrap();
+
+        assert_eq!(
+            error("color = 1 2 3"),
+            "line 1: 'color' is outside of any [material]"
+        );
+        assert_eq!(
+            error("[a]\ntype = lambertian\ncolor"),
+            "line 3: expected 'property = value', got 'color'"
+        );
+        assert_eq!(error("[a]\nbase = b"), "line 2: unknown base material 'b'");
+        assert_eq!(
+            error("[a]\ntype = lambertian\nroughness = 0.5"),
+            "material 'a' of type lambertian has no property 'roughness'"
+        );
+        assert_eq!(
+            error("[a]\ntype = pbr\ncolor = 255 0"),
+            "material 'a' has invalid color '255 0', expected 3 numbers"
+        );
+        assert!(error("[a]\ntype = velvet").contains("unknown type 'velvet'"));
+        assert_eq!(
+            error("[a]\ntype = dielectric\nabbe = 40\ncauchy = 1.5 0.004"),
+            "material 'a' has more than one of abbe, cauchy and sellmeier"
+        );
+        assert!(error("[a]\ntype = dielectric\nabbe = 0").contains("invalid abbe"));
+        assert_eq!(
+            error("[a]\ntype = lambertian\nopacity_pattern = lace"),
+            "material 'a' has invalid opacity_pattern 'lace', expected checker, noise, \
+             turbulence or voronoi"
+        );
+    }
+
+    #[test]
+    fn textured() {
+        let library = MaterialLibrary::builtin();
+        let texture = || {
+            let texels = vec![Vec3::new(0.5, 0.5, 0.5)];
+            Arc::new(ImageTexture::new(1, 1, texels, (Wrap::Repeat, Wrap::Repeat)))
+        };
+
+        assert!(library.textured("red_plastic", texture()).is_ok());
+        assert_eq!(
+            library.textured("wax", texture()).err().unwrap(),
+            "material 'wax' can't be textured"
+        );
+        assert!(library.get("wax").is_ok());
+    }
+}
@@ -0,0 +1,28 @@
+use crate::geometry::material::Material;
+use crate::geometry::mesh::Mesh;
+
+use std::fs;
+use std::path::Path;
+
+pub mod gltf;
+pub mod ies;
+pub mod materials;
+pub mod ply;
+pub mod stl;
+
+// Picks the loader by file extension.
+pub fn load_mesh(path: &str, material: Box<dyn Material>) -> Result<Mesh, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    let mesh = match extension.as_deref() {
+        Some("ply") => ply::parse(&bytes, material),
+        Some("stl") => stl::parse(&bytes, material),
+        _ => return Err(format!("Unsupported mesh format: {}", path)),
+    };
+
+    mesh.map_err(|e| format!("Can't load {}: {}", path, e))
+}
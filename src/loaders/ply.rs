@@ -0,0 +1,592 @@
+use crate::geometry::material::Material;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::utils::rgb::RGB;
+
+use std::convert::TryInto;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "char" | "int8" => Ok(ScalarType::Char),
+            "uchar" | "uint8" => Ok(ScalarType::UChar),
+            "short" | "int16" => Ok(ScalarType::Short),
+            "ushort" | "uint16" => Ok(ScalarType::UShort),
+            "int" | "int32" => Ok(ScalarType::Int),
+            "uint" | "uint32" => Ok(ScalarType::UInt),
+            "float" | "float32" => Ok(ScalarType::Float),
+            "double" | "float64" => Ok(ScalarType::Double),
+            _ => Err(format!("unknown property type '{}'", name)),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Char | ScalarType::UChar => 1,
+            ScalarType::Short | ScalarType::UShort => 2,
+            ScalarType::Int | ScalarType::UInt | ScalarType::Float => 4,
+            ScalarType::Double => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, ScalarType::Float | ScalarType::Double)
+    }
+}
+
+enum Property {
+    Scalar {
+        name: String,
+        value_type: ScalarType,
+    },
+    List {
+        name: String,
+        count_type: ScalarType,
+        item_type: ScalarType,
+    },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property_index(&self, name: &str) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| property.name() == name)
+    }
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    body_start: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+
+    loop {
+        let line_end = bytes[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or("header is not terminated by end_header")?;
+        let line = std::str::from_utf8(&bytes[offset..offset + line_end])
+            .map_err(|_| format!("header line {} is not valid text", line_number + 1))?
+            .trim();
+        offset += line_end + 1;
+        line_number += 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = || format!("malformed header line {}: '{}'", line_number, line);
+
+        if line_number == 1 {
+            if line != "ply" {
+                return Err(String::from("missing 'ply' magic number"));
+            }
+            continue;
+        }
+
+        match tokens.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(format!("unsupported format '{}'", kind)),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error())?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => elements
+                .last_mut()
+                .ok_or_else(error)?
+                .properties
+                .push(Property::List {
+                    name: name.to_string(),
+                    count_type: ScalarType::parse(count_type)?,
+                    item_type: ScalarType::parse(item_type)?,
+                }),
+            ["property", value_type, name] => elements
+                .last_mut()
+                .ok_or_else(error)?
+                .properties
+                .push(Property::Scalar {
+                    name: name.to_string(),
+                    value_type: ScalarType::parse(value_type)?,
+                }),
+            ["end_header"] => break,
+            _ => return Err(error()),
+        }
+    }
+
+    Ok(Header {
+        format: format.ok_or("missing format line")?,
+        elements,
+        body_start: offset,
+    })
+}
+
+// Yields property values one by one regardless of how the body is encoded.
+enum Body<'a> {
+    Ascii {
+        text: &'a str,
+        offset: usize,
+    },
+    Binary {
+        bytes: &'a [u8],
+        offset: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Body<'a> {
+    fn new(bytes: &'a [u8], format: Format) -> Result<Self, String> {
+        match format {
+            Format::Ascii => {
+                let text = std::str::from_utf8(bytes).map_err(|_| "body is not valid text")?;
+                Ok(Body::Ascii { text, offset: 0 })
+            }
+            _ => Ok(Body::Binary {
+                bytes,
+                offset: 0,
+                big_endian: format == Format::BinaryBigEndian,
+            }),
+        }
+    }
+
+    fn read(&mut self, value_type: ScalarType) -> Option<Result<f64, String>> {
+        match self {
+            Body::Ascii { text, offset } => {
+                let rest = &text[*offset..];
+                let start = rest.find(|c: char| !c.is_ascii_whitespace())?;
+                let length = rest[start..]
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(rest.len() - start);
+                let token = &rest[start..start + length];
+                *offset += start + length;
+
+                Some(
+                    token
+                        .parse::<f64>()
+                        .map_err(|_| format!("invalid number '{}'", token)),
+                )
+            }
+            Body::Binary {
+                bytes,
+                offset,
+                big_endian,
+            } => {
+                let size = value_type.size();
+                let chunk = bytes.get(*offset..*offset + size)?;
+                *offset += size;
+
+                Some(Ok(decode(chunk, value_type, *big_endian)))
+            }
+        }
+    }
+
+    // Most values of the type the rest of the body could hold. Text ones take at least a
+    // character and a space each.
+    fn room(&self, value_type: ScalarType) -> usize {
+        match self {
+            Body::Ascii { text, offset } => (text.len() - offset).div_ceil(2),
+            Body::Binary { bytes, offset, .. } => (bytes.len() - offset) / value_type.size(),
+        }
+    }
+}
+
+fn decode(chunk: &[u8], value_type: ScalarType, big_endian: bool) -> f64 {
+    macro_rules! number {
+        ($t:ty) => {{
+            let array = chunk.try_into().unwrap();
+            if big_endian {
+                <$t>::from_be_bytes(array) as f64
+            } else {
+                <$t>::from_le_bytes(array) as f64
+            }
+        }};
+    }
+
+    match value_type {
+        ScalarType::Char => number!(i8),
+        ScalarType::UChar => number!(u8),
+        ScalarType::Short => number!(i16),
+        ScalarType::UShort => number!(u16),
+        ScalarType::Int => number!(i32),
+        ScalarType::UInt => number!(u32),
+        ScalarType::Float => number!(f32),
+        ScalarType::Double => number!(f64),
+    }
+}
+
+fn color_channel(value: f64, value_type: ScalarType) -> u8 {
+    let value = if value_type.is_float() {
+        value * 255.
+    } else {
+        value
+    };
+
+    value.round().clamp(0., 255.) as u8
+}
+
+// Reads ASCII and binary PLY files. Faces with more than three vertices are triangulated as fans.
+pub fn parse(bytes: &[u8], material: Box<dyn Material>) -> Result<Mesh, String> {
+    let header = parse_header(bytes)?;
+    let mut body = Body::new(&bytes[header.body_start..], header.format)?;
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut triangles = Vec::new();
+    let mut has_normals = false;
+    let mut has_colors = false;
+    let mut has_uvs = false;
+
+    for element in &header.elements {
+        let position = ["x", "y", "z"]
+            .iter()
+            .map(|name| element.property_index(name))
+            .collect::<Option<Vec<usize>>>();
+        let normal = ["nx", "ny", "nz"]
+            .iter()
+            .map(|name| element.property_index(name))
+            .collect::<Option<Vec<usize>>>();
+        let color = ["red", "green", "blue"]
+            .iter()
+            .map(|name| element.property_index(name))
+            .collect::<Option<Vec<usize>>>();
+        let uv = [["u", "v"], ["s", "t"], ["texture_u", "texture_v"]]
+            .iter()
+            .find_map(|names| {
+                names
+                    .iter()
+                    .map(|name| element.property_index(name))
+                    .collect::<Option<Vec<usize>>>()
+            });
+        let face_indices = element
+            .property_index("vertex_indices")
+            .or_else(|| element.property_index("vertex_index"));
+
+        if element.name == "vertex" {
+            if position.is_none() {
+                return Err(String::from("vertex element has no x, y, z properties"));
+            }
+            has_normals = normal.is_some();
+            has_colors = color.is_some();
+            has_uvs = uv.is_some();
+        }
+
+        for record in 0..element.count {
+            let truncated = || {
+                format!(
+                    "file is truncated in element '{}' #{} of {}",
+                    element.name, record, element.count
+                )
+            };
+            let mut values = Vec::with_capacity(element.properties.len());
+            let mut list = Vec::new();
+
+            for (index, property) in element.properties.iter().enumerate() {
+                match property {
+                    Property::Scalar { value_type, .. } => {
+                        values.push(body.read(*value_type).ok_or_else(truncated)??);
+                    }
+                    Property::List {
+                        count_type,
+                        item_type,
+                        ..
+                    } => {
+                        let count = body.read(*count_type).ok_or_else(truncated)??;
+                        // Checked before anything is allocated or read for it.
+                        if !(count >= 0. && count.fract() == 0.) {
+                            return Err(format!(
+                                "invalid list length {} in element '{}' #{} of {}",
+                                count, element.name, record, element.count
+                            ));
+                        }
+                        if count > body.room(*item_type) as f64 {
+                            return Err(truncated());
+                        }
+                        let mut items = Vec::new();
+                        for _ in 0..count as usize {
+                            items.push(body.read(*item_type).ok_or_else(truncated)??);
+                        }
+                        if Some(index) == face_indices {
+                            list = items;
+                        }
+                        values.push(0.);
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let at = |indices: &Vec<usize>, axis: usize| values[indices[axis]];
+                    let position = position.as_ref().unwrap();
+                    vertices.push(Vec3::new(
+                        at(position, 0) as Float,
+                        at(position, 1) as Float,
+                        at(position, 2) as Float,
+                    ));
+
+                    if let Some(normal) = &normal {
+                        normals.push(
+                            Vec3::new(
+                                at(normal, 0) as Float,
+                                at(normal, 1) as Float,
+                                at(normal, 2) as Float,
+                            )
+                            .normalize(),
+                        );
+                    }
+
+                    if let Some(color) = &color {
+                        let channel = |axis: usize| match element.properties[color[axis]] {
+                            Property::Scalar { value_type, .. } => {
+                                color_channel(at(color, axis), value_type)
+                            }
+                            Property::List { .. } => 0,
+                        };
+                        colors.push(RGB::new(channel(0), channel(1), channel(2)));
+                    }
+
+                    // PLY counts v from the bottom of the image, the renderer from the top.
+                    if let Some(uv) = &uv {
+                        uvs.push([at(uv, 0) as Float, 1. - at(uv, 1) as Float]);
+                    }
+                }
+                "face" => {
+                    if face_indices.is_none() {
+                        return Err(String::from("face element has no vertex_indices list"));
+                    }
+
+                    if list.iter().any(|&index| !(index >= 0. && index.fract() == 0.)) {
+                        return Err(format!("face #{} has invalid vertex index", record));
+                    }
+                    let indices: Vec<usize> = list.iter().map(|&index| index as usize).collect();
+                    for i in 1..indices.len().saturating_sub(1) {
+                        triangles.push([indices[0], indices[i], indices[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if triangles.is_empty() {
+        return Err(String::from("file contains no faces"));
+    }
+
+    for (face, triangle) in triangles.iter().enumerate() {
+        if let Some(index) = triangle.iter().find(|&&index| index >= vertices.len()) {
+            return Err(format!(
+                "face #{} references vertex {} but only {} vertices exist",
+                face,
+                index,
+                vertices.len()
+            ));
+        }
+    }
+
+    let mesh = Mesh::new(
+        vertices,
+        if has_normals { Some(normals) } else { None },
+        if has_colors { Some(colors) } else { None },
+        triangles,
+        material,
+    );
+
+    Ok(if has_uvs { mesh.with_uvs(uvs) } else { mesh })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::Material;
+    use crate::loaders::ply;
+    use crate::utils::rgb::RGB;
+
+    fn material() -> Box<dyn Material> {
+        Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        })
+    }
+
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 255 0 0
+1 1 0 255 0 0
+0 1 0 255 0 0
+4 0 1 2 3
+";
+
+    #[test]
+    fn parse_ascii() {
+        let mesh = ply::parse(ASCII_QUAD.as_bytes(), material()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.colors.unwrap()[2].r, 255);
+        assert!(mesh.normals.is_none());
+        assert!(mesh.uvs.is_none());
+    }
+
+    #[test]
+    fn parse_texture_coordinates() {
+        let triangle = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float s
+property float t
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0
+1 0 0 1 0
+0 1 0 0 1
+3 0 1 2
+";
+        let mesh = ply::parse(triangle.as_bytes(), material()).unwrap();
+
+        assert_eq!(mesh.uvs.unwrap(), vec![[0., 1.], [1., 1.], [0., 0.]]);
+    }
+
+    #[test]
+    fn parse_binary() {
+        let mut bytes = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+        .to_vec();
+        for vertex in &[[0f32, 0., 0.], [1., 0., 0.], [0., 1., 0.]] {
+            for value in vertex.iter().chain([0f32, 0., 1.].iter()) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes.push(3);
+        for index in 0u32..3 {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let mesh = ply::parse(&bytes, material()).unwrap();
+
+        assert_eq!(mesh.vertices[1].x, 1.);
+        assert_eq!(mesh.normals.unwrap()[2].z, 1.);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2]]);
+
+        let truncated = ply::parse(&bytes[..bytes.len() - 2], material());
+        assert!(truncated.err().unwrap().contains("truncated"));
+    }
+
+    #[test]
+    fn parse_malformed() {
+        let missing_vertex = ASCII_QUAD.replace("4 0 1 2 3", "3 0 1 7");
+        let error = ply::parse(missing_vertex.as_bytes(), material()).err().unwrap();
+        assert!(error.contains("references vertex 7"));
+
+        for index in &["-1", "0.5", "nan", "inf"] {
+            let bad_index = ASCII_QUAD.replace("4 0 1 2 3", &format!("4 0 1 {} 3", index));
+            let error = ply::parse(bad_index.as_bytes(), material()).err().unwrap();
+            assert_eq!(error, "face #0 has invalid vertex index");
+        }
+
+        let bad_number = ASCII_QUAD.replace("1 1 0 255", "1 oops 0 255");
+        assert!(ply::parse(bad_number.as_bytes(), material()).is_err());
+
+        assert!(ply::parse(b"obj\n", material()).is_err());
+
+        let huge_list = ASCII_QUAD.replace("4 0 1 2 3", "1e20 0 1 2 3");
+        let error = ply::parse(huge_list.as_bytes(), material()).err().unwrap();
+        assert_eq!(error, "file is truncated in element 'face' #0 of 1");
+        for count in &["-4", "2.5", "inf"] {
+            let bad_list = ASCII_QUAD.replace("4 0 1 2 3", &format!("{} 0 1 2 3", count));
+            let error = ply::parse(bad_list.as_bytes(), material()).err().unwrap();
+            assert!(error.starts_with("invalid list length"), "{}", error);
+        }
+
+        let mut bytes = b"ply
+format binary_big_endian 1.0
+element vertex 0
+property float x
+property float y
+property float z
+element face 1
+property list uint uint vertex_indices
+end_header
+"
+        .to_vec();
+        bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+        let error = ply::parse(&bytes, material()).err().unwrap();
+        assert_eq!(error, "file is truncated in element 'face' #0 of 1");
+    }
+}
@@ -0,0 +1,224 @@
+use crate::geometry::material::Material;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+use std::convert::TryInto;
+
+const HEADER_SIZE: usize = 80;
+const TRIANGLE_SIZE: usize = 50;
+
+// Reads ASCII and binary STL. Every facet gets its own vertices and is shaded flat.
+pub fn parse(bytes: &[u8], material: Box<dyn Material>) -> Result<Mesh, String> {
+    // Some exporters start binary files with "solid" too, so trust the size check first.
+    let facets = if is_binary(bytes) || !bytes.starts_with(b"solid") {
+        parse_binary(bytes)?
+    } else {
+        parse_ascii(bytes)?
+    };
+
+    if facets.is_empty() {
+        return Err(String::from("file contains no facets"));
+    }
+
+    let mut vertices = Vec::with_capacity(facets.len() * 3);
+    let mut triangles = Vec::with_capacity(facets.len());
+
+    for (normal, [a, b, c]) in facets {
+        let start = vertices.len();
+        let winding = (b - a).cross(&(c - a));
+
+        // Facet normals are optional in practice, but when given they win over the winding.
+        if normal.dot_product(&winding) < 0. {
+            triangles.push([start, start + 2, start + 1]);
+        } else {
+            triangles.push([start, start + 1, start + 2]);
+        }
+        vertices.push(a);
+        vertices.push(b);
+        vertices.push(c);
+    }
+
+    Ok(Mesh::new(vertices, None, None, triangles, material))
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_SIZE + 4
+        && HEADER_SIZE + 4 + triangle_count(bytes) * TRIANGLE_SIZE == bytes.len()
+}
+
+fn triangle_count(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(bytes[HEADER_SIZE..HEADER_SIZE + 4].try_into().unwrap()) as usize
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<(Vec3, [Vec3; 3])>, String> {
+    if bytes.len() < HEADER_SIZE + 4 {
+        return Err(String::from("file is truncated before the triangle count"));
+    }
+
+    let count = triangle_count(bytes);
+    let expected = HEADER_SIZE + 4 + count * TRIANGLE_SIZE;
+    if bytes.len() < expected {
+        return Err(format!(
+            "file is truncated: {} triangles need {} bytes but only {} are present",
+            count,
+            expected,
+            bytes.len()
+        ));
+    }
+
+    let read_vec = |offset: usize| {
+        let value = |index: usize| {
+            let start = offset + index * 4;
+            f32::from_le_bytes(bytes[start..start + 4].try_into().unwrap())
+        };
+        Vec3::new(value(0) as Float, value(1) as Float, value(2) as Float)
+    };
+
+    Ok((0..count)
+        .map(|index| {
+            let offset = HEADER_SIZE + 4 + index * TRIANGLE_SIZE;
+            (
+                read_vec(offset),
+                [
+                    read_vec(offset + 12),
+                    read_vec(offset + 24),
+                    read_vec(offset + 36),
+                ],
+            )
+        })
+        .collect())
+}
+
+fn parse_ascii(bytes: &[u8]) -> Result<Vec<(Vec3, [Vec3; 3])>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "ASCII file is not valid text")?;
+    let mut tokens = text.split_ascii_whitespace().peekable();
+    let mut facets = Vec::new();
+
+    let expect = |tokens: &mut std::iter::Peekable<std::str::SplitAsciiWhitespace>,
+                      keyword: &str,
+                      facet: usize| match tokens.next() {
+        Some(token) if token == keyword => Ok(()),
+        Some(token) => Err(format!(
+            "expected '{}' but found '{}' in facet #{}",
+            keyword, token, facet
+        )),
+        None => Err(format!("file is truncated in facet #{}", facet)),
+    };
+    let read_vec = |tokens: &mut std::iter::Peekable<std::str::SplitAsciiWhitespace>,
+                    facet: usize|
+     -> Result<Vec3, String> {
+        let mut value = || match tokens.next() {
+            Some(token) => token
+                .parse::<Float>()
+                .map_err(|_| format!("invalid number '{}' in facet #{}", token, facet)),
+            None => Err(format!("file is truncated in facet #{}", facet)),
+        };
+        Ok(Vec3::new(value()?, value()?, value()?))
+    };
+
+    expect(&mut tokens, "solid", 0)?;
+    // The solid name is free text, skip up to the first facet.
+    while let Some(&token) = tokens.peek() {
+        if token == "facet" || token == "endsolid" {
+            break;
+        }
+        tokens.next();
+    }
+
+    loop {
+        let facet = facets.len();
+
+        match tokens.next() {
+            Some("facet") => {}
+            Some("endsolid") => break,
+            Some(token) => {
+                return Err(format!(
+                    "expected 'facet' but found '{}' after facet #{}",
+                    token, facet
+                ))
+            }
+            None => return Err(String::from("file is truncated, missing 'endsolid'")),
+        }
+
+        expect(&mut tokens, "normal", facet)?;
+        let normal = read_vec(&mut tokens, facet)?;
+        expect(&mut tokens, "outer", facet)?;
+        expect(&mut tokens, "loop", facet)?;
+        expect(&mut tokens, "vertex", facet)?;
+        let a = read_vec(&mut tokens, facet)?;
+        expect(&mut tokens, "vertex", facet)?;
+        let b = read_vec(&mut tokens, facet)?;
+        expect(&mut tokens, "vertex", facet)?;
+        let c = read_vec(&mut tokens, facet)?;
+        expect(&mut tokens, "endloop", facet)?;
+        expect(&mut tokens, "endfacet", facet)?;
+
+        facets.push((normal, [a, b, c]));
+    }
+
+    Ok(facets)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::phong::Phong;
+    use crate::geometry::material::Material;
+    use crate::loaders::stl;
+    use crate::utils::rgb::RGB;
+
+    fn material() -> Box<dyn Material> {
+        Box::new(Phong {
+            color: RGB::new(24, 24, 24),
+            specular_exponent: 1.,
+            albedo: (1., 1., 1., 1.),
+            refractive_index: 0.,
+            color_texture: None,
+            specular_texture: None,
+        })
+    }
+
+    const ASCII_TRIANGLE: &str = "solid test part
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid test part
+";
+
+    #[test]
+    fn parse_ascii() {
+        let mesh = stl::parse(ASCII_TRIANGLE.as_bytes(), material()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 3);
+        // The facet normal points down, so the winding is flipped to match it.
+        assert_eq!(mesh.triangles, vec![[0, 2, 1]]);
+
+        let truncated = &ASCII_TRIANGLE[..ASCII_TRIANGLE.find("endloop").unwrap()];
+        let error = stl::parse(truncated.as_bytes(), material()).err().unwrap();
+        assert!(error.contains("truncated"));
+    }
+
+    #[test]
+    fn parse_binary() {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        for value in &[0f32, 0., 1., 0., 0., 0., 1., 0., 0., 0., 1., 0.] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0]);
+
+        let mesh = stl::parse(&bytes, material()).unwrap();
+
+        assert_eq!(mesh.vertices[2].y, 1.);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2]]);
+
+        let error = stl::parse(&bytes[..bytes.len() - 10], material())
+            .err()
+            .unwrap();
+        assert!(error.contains("truncated"));
+    }
+}
@@ -2,26 +2,38 @@ extern crate image;
 extern crate num_cpus;
 
 mod geometry;
+mod loaders;
+mod math;
 mod renderer;
 mod scene;
 mod sdl;
 mod utils;
 
+use geometry::Float;
+use geometry::consts::{FRAC_PI_2, FRAC_PI_3};
+use geometry::heightfield::Heightfield;
+use geometry::material::procedural::{Pattern, Procedural, Space};
+use geometry::material::texture::{ImageTexture, Wrap};
+use geometry::metaball::{Metaball, Metaballs};
+use geometry::motion::Moving;
+use geometry::object::Object;
 use geometry::sphere::Sphere;
+use geometry::sphere_set::SphereSet;
 use geometry::vec3::Vec3;
+use loaders::materials::MaterialLibrary;
 use scene::camera::Camera;
-use scene::light::Light;
-use scene::{Canvas, Scene, SceneOptions};
+use scene::light::{Emitters, Light};
+use scene::{Canvas, Integrator, Scene, SceneOptions};
 use std::env;
+use std::io::{self, Read};
 use std::sync::Arc;
 use std::time::Instant;
 use threadpool::ThreadPool;
-use utils::material_factory;
 use utils::rgb::RGB;
 
 const HEIGHT: usize = 720;
 const WIDTH: usize = 1280;
-const FOV: f32 = 3.14 / 3.;
+const FOV: Float = FRAC_PI_3;
 const BACKGROUND_COLOR: RGB = RGB::new(178, 178, 178);
 const MAX_REFLECTIONS_ALLOWED: usize = 4;
 
@@ -31,18 +43,67 @@ fn main() -> Result<(), String> {
 
     let is_static_render = args.contains(&String::from("-static"));
     let disable_parallelization = args.contains(&String::from("-no-parallel"));
+    let packet_tracing = !args.contains(&String::from("-no-packets"));
+    let integrator = if args.contains(&String::from("-path-tracing")) {
+        Integrator::PathTracing
+    } else {
+        Integrator::Whitted
+    };
+    let terrain_path = get_option_value(&args, "-terrain");
+    let mesh_path = get_option_value(&args, "-mesh");
+    let texture_path = get_option_value(&args, "-texture");
+    let fixture_path = get_option_value(&args, "-ies");
+    let mesh_material = get_option_value(&args, "-mesh-material").unwrap_or("dark_green_plastic");
+    let samples_per_pixel = match get_option_value(&args, "-samples") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid sample count: {}", value))?,
+        None => 1,
+    };
+    let options = create_options(samples_per_pixel, packet_tracing, integrator);
+    let materials = match get_option_value(&args, "-materials") {
+        Some(path) => MaterialLibrary::load(path)?,
+        None => MaterialLibrary::builtin(),
+    };
+
+    let scene = match get_option_value(&args, "-gltf") {
+        // "-" reads the scene from the standard input.
+        Some("-") => {
+            let mut bytes = Vec::new();
+            io::stdin()
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("Can't read the standard input: {}", e))?;
+            loaders::gltf::parse_scene(&bytes, create_canvas(), options, &materials)?
+        }
+        Some(path) => loaders::gltf::load_scene(path, create_canvas(), options, &materials)?,
+        None => create_scene(
+            terrain_path,
+            mesh_path.map(|path| (path, mesh_material)),
+            texture_path,
+            fixture_path,
+            &materials,
+            options,
+        )?,
+    };
 
     if is_static_render {
-        run_static(disable_parallelization);
+        run_static(scene, disable_parallelization);
     } else {
-        run_dynamic(disable_parallelization);
+        run_dynamic(scene, disable_parallelization);
     }
 
     Ok(())
 }
 
-fn run_static(disable_parallelization: bool) {
-    let scene = Arc::new(create_scene());
+fn get_option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .map(|value| value.as_str())
+}
+
+fn run_static(scene: Scene, disable_parallelization: bool) {
+    let scene = Arc::new(scene);
     let start = Instant::now();
 
     render_static(&scene, disable_parallelization);
@@ -59,61 +120,185 @@ fn render_static(scene: &Arc<Scene>, no_parallel: bool) {
     buffer.save("test.png").unwrap();
 }
 
-fn run_dynamic(disable_parallelization: bool) {
-    sdl::run_sdl(create_scene(), disable_parallelization);
+fn run_dynamic(scene: Scene, disable_parallelization: bool) {
+    sdl::run_sdl(scene, disable_parallelization);
 }
 
-fn create_scene() -> Scene {
-    let camera = Camera::new(Vec3::new(0., 0., 0.));
+// `mesh` is the path of a mesh file and the name of its material. `fixture_path` is an IES
+// file for a spot light over the row of material spheres.
+fn create_scene(
+    terrain_path: Option<&str>,
+    mesh: Option<(&str, &str)>,
+    texture_path: Option<&str>,
+    fixture_path: Option<&str>,
+    materials: &MaterialLibrary,
+    options: SceneOptions,
+) -> Result<Scene, String> {
+    let camera = Camera::looking_at(
+        Vec3::new(0., 0., 0.),
+        &Vec3::new(0., 0., -1.),
+        &Vec3::new(0., 1., 0.),
+    );
 
-    let mut objects = vec![
-        Sphere::new(
-            Vec3::new(-3.0, 0., -16.0),
-            2.0,
-            material_factory::get_dark_green_plastic(),
-        ),
-        Sphere::new(
-            Vec3::new(-1.0, -1.5, -12.0),
-            2.0,
-            material_factory::get_glass(),
-        ),
-        Sphere::new(
-            Vec3::new(1.5, -0.5, -18.0),
-            3.0,
-            material_factory::get_red_rubber(),
-        ),
-        Sphere::new(
-            Vec3::new(7., 5., -18.0),
-            4.0,
-            material_factory::get_mirror(),
+    let mut spheres = SphereSet::new();
+    spheres.push(Sphere::new(
+        Vec3::new(-3.0, 0., -16.0),
+        2.0,
+        materials.get("dark_green_plastic")?,
+    ));
+    spheres.push(Sphere::new(
+        Vec3::new(-1.0, -1.5, -12.0),
+        2.0,
+        materials.get("glass")?,
+    ));
+    spheres.push(Sphere::new(
+        Vec3::new(7., 5., -18.0),
+        4.0,
+        materials.get("mirror")?,
+    ));
+    spheres.push(Sphere::new(
+        Vec3::new(-7., -4., -15.),
+        1.8,
+        materials.get("gold")?,
+    ));
+    spheres.push(Sphere::new(
+        Vec3::new(-3.2, -4.7, -13.),
+        0.35,
+        materials.get("light_bulb")?,
+    ));
+
+    let showcase = [
+        "clay",
+        "hammered_copper",
+        "crystal",
+        "silver_mirror",
+        "soap_bubble",
+        "car_paint",
+        "wax",
+    ];
+    for (i, name) in showcase.iter().enumerate() {
+        let x = -4.5 + 2.5 * i as Float;
+        spheres.push(Sphere::new(Vec3::new(x, -6., -14.), 1., materials.get(name)?));
+    }
+
+    let patterns = vec![
+        (Pattern::Checker, RGB::new(40, 40, 40), RGB::new(235, 235, 235), 1.5),
+        (
+            Pattern::Gradient(Vec3::new(0., -0.4, 0.)),
+            RGB::new(255, 140, 20),
+            RGB::new(40, 30, 120),
+            1.,
         ),
+        (Pattern::Noise, RGB::new(20, 60, 20), RGB::new(140, 200, 90), 3.),
+        (Pattern::Turbulence, RGB::new(240, 240, 255), RGB::new(60, 70, 110), 2.),
+        (Pattern::Marble(6.), RGB::new(60, 60, 70), RGB::new(240, 238, 230), 1.),
+        (Pattern::Wood(0.4), RGB::new(160, 100, 50), RGB::new(90, 50, 20), 4.),
+        (Pattern::Voronoi, RGB::new(250, 220, 120), RGB::new(120, 40, 10), 2.5),
     ];
+    for (i, (pattern, low, high, scale)) in patterns.into_iter().enumerate() {
+        let texture = Procedural {
+            pattern,
+            colors: (low, high),
+            scale,
+            space: if i == 0 { Space::World } else { Space::Object },
+        };
+        let x = -15. + 2.8 * i as Float;
+        spheres.push(Sphere::new(
+            Vec3::new(x, 8., -22.),
+            1.2,
+            materials.textured("paint", Arc::new(texture))?,
+        ));
+    }
 
-    objects.sort_by(|sphere1, sphere2| {
-        let a = sphere1.center.minus(&camera.position).length();
-        let b = sphere2.center.minus(&camera.position).length();
+    if let Some(path) = texture_path {
+        let texture = ImageTexture::load(path, (Wrap::Repeat, Wrap::ClampToEdge))?;
+        spheres.push(Sphere::new(
+            Vec3::new(-9., 3., -20.),
+            2.5,
+            materials.textured("paint", Arc::new(texture))?,
+        ));
+    }
 
-        a.partial_cmp(&b).unwrap()
-    });
+    let mut objects: Vec<Box<dyn Object>> = vec![
+        Box::new(spheres),
+        Box::new(Moving::new(
+            Box::new(Sphere::new(
+                Vec3::new(1.5, -0.5, -18.0),
+                3.0,
+                materials.get("red_rubber")?,
+            )),
+            Vec3::new(1., 0., 0.),
+        )),
+        Box::new(Metaballs::new(
+            vec![
+                Metaball::new(Vec3::new(3.5, -3., -11.), 1.5, 1.),
+                Metaball::new(Vec3::new(4.8, -2.6, -11.5), 1.3, 1.),
+                Metaball::new(Vec3::new(4.2, -1.8, -10.5), 1., 1.),
+            ],
+            0.2,
+            materials.get("glass")?,
+        )),
+    ];
+
+    if let Some(path) = terrain_path {
+        objects.push(Box::new(Heightfield::from_image(
+            path,
+            Vec3::new(-50., -6., -60.),
+            Vec3::new(0.5, 6., 0.5),
+            materials.get("red_rubber")?,
+        )?));
+    }
 
-    let lights = vec![
+    if let Some((path, material)) = mesh {
+        let mesh = loaders::load_mesh(path, materials.get(material)?)?;
+        objects.push(Box::new(mesh.placed(&Vec3::new(-6., -4., -14.), 1.)));
+    }
+
+    let mut lights = vec![
         Light::new(Vec3::new(-20., 20., 20.), 1.5),
         Light::new(Vec3::new(30., 50., -25.), 1.8),
         Light::new(Vec3::new(30., 20., 30.), 1.7),
     ];
 
-    Scene {
+    if let Some(path) = fixture_path {
+        let spot = Light::spot(
+            Vec3::new(-3., -3.5, -10.),
+            Vec3::new(0.5, -1., -1.5),
+            FRAC_PI_2,
+            FRAC_PI_2,
+            2.,
+        );
+        lights.push(spot.with_profile(loaders::ies::load(path)?));
+    }
+
+    Ok(Scene {
         lights,
+        emitters: Emitters::new(&objects),
         camera,
         objects,
-        options: SceneOptions {
-            reflections_limit: MAX_REFLECTIONS_ALLOWED,
-            background_color: BACKGROUND_COLOR,
-        },
-        canvas: Canvas {
-            width: WIDTH,
-            height: HEIGHT,
-            fov: FOV,
-        },
+        options,
+        canvas: create_canvas(),
+    })
+}
+
+fn create_options(
+    samples_per_pixel: usize,
+    packet_tracing: bool,
+    integrator: Integrator,
+) -> SceneOptions {
+    SceneOptions {
+        reflections_limit: MAX_REFLECTIONS_ALLOWED,
+        background_color: BACKGROUND_COLOR,
+        samples_per_pixel,
+        packet_tracing,
+        integrator,
+    }
+}
+
+fn create_canvas() -> Canvas {
+    Canvas {
+        width: WIDTH,
+        height: HEIGHT,
+        fov: FOV,
     }
 }
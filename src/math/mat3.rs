@@ -0,0 +1,213 @@
+#[cfg(test)]
+use crate::geometry::vec3::ApproxEq;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+use std::ops::Mul;
+
+// Row-major, multiplies column vectors from the left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    pub rows: [[Float; 3]; 3],
+}
+
+impl Mat3 {
+    pub const fn new(rows: [[Float; 3]; 3]) -> Self {
+        Self { rows }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]])
+    }
+
+    pub fn from_columns(x: &Vec3, y: &Vec3, z: &Vec3) -> Self {
+        Self::new([[x.x, y.x, z.x], [x.y, y.y, z.y], [x.z, y.z, z.z]])
+    }
+
+    pub fn scaling(scale: &Vec3) -> Self {
+        Self::new([[scale.x, 0., 0.], [0., scale.y, 0.], [0., 0., scale.z]])
+    }
+
+    pub fn row(&self, index: usize) -> Vec3 {
+        let row = self.rows[index];
+        Vec3::new(row[0], row[1], row[2])
+    }
+
+    pub fn column(&self, index: usize) -> Vec3 {
+        Vec3::new(
+            self.rows[0][index],
+            self.rows[1][index],
+            self.rows[2][index],
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_columns(&self.row(0), &self.row(1), &self.row(2))
+    }
+
+    pub fn determinant(&self) -> Float {
+        self.column(0)
+            .dot_product(&self.column(1).cross(&self.column(2)))
+    }
+
+    // The inverse transpose scaled by the determinant. Unlike the inverse it always exists,
+    // which makes it the safe way to transform normals.
+    pub fn cofactor(&self) -> Self {
+        let (a, b, c) = (self.column(0), self.column(1), self.column(2));
+
+        Self::from_columns(&b.cross(&c), &c.cross(&a), &a.cross(&b))
+    }
+
+    // None for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0. {
+            return None;
+        }
+
+        Some(self.cofactor().transpose() * (1. / determinant))
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, matrix: Self) -> Self {
+        let mut rows = [[0.; 3]; 3];
+
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.row(i).dot_product(&matrix.column(j));
+            }
+        }
+
+        Self::new(rows)
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, vec: Vec3) -> Vec3 {
+        Vec3::new(
+            self.row(0).dot_product(&vec),
+            self.row(1).dot_product(&vec),
+            self.row(2).dot_product(&vec),
+        )
+    }
+}
+
+impl Mul<Float> for Mat3 {
+    type Output = Self;
+
+    fn mul(self, factor: Float) -> Self {
+        let mut rows = self.rows;
+        for value in rows.iter_mut().flatten() {
+            *value *= factor;
+        }
+
+        Self::new(rows)
+    }
+}
+
+#[cfg(test)]
+impl ApproxEq for Mat3 {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        (0..3).all(|i| self.row(i).approx_eq(&other.row(i), epsilon))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::math::mat3::Mat3;
+
+    fn sample() -> Mat3 {
+        Mat3::new([[1., 2., 3.], [0., 1., 4.], [5., 6., 0.]])
+    }
+
+    #[test]
+    fn multiply() {
+        let a = Mat3::new([[1., 2., 3.], [4., 5., 6.], [3., 2., 2.]]);
+        let b = Mat3::new([[2., 3., 3.], [1., 3., 2.], [1., 1., 3.]]);
+
+        assert_eq!(
+            a * b,
+            Mat3::new([[7., 12., 16.], [19., 33., 40.], [10., 17., 19.]])
+        );
+        assert_eq!(a * Mat3::identity(), a);
+    }
+
+    #[test]
+    fn multiply_vector() {
+        let matrix = Mat3::new([[1., 2., 3.], [3., 2., 1.], [4., 1., 3.]]);
+
+        assert_eq!(matrix * Vec3::new(2., 3., 4.), Vec3::new(20., 16., 23.));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let matrix = sample();
+
+        assert_eq!(matrix.row(1), Vec3::new(0., 1., 4.));
+        assert_eq!(matrix.column(1), Vec3::new(2., 1., 6.));
+        assert_eq!(
+            Mat3::from_columns(&matrix.column(0), &matrix.column(1), &matrix.column(2)),
+            matrix
+        );
+        assert_eq!(matrix.transpose().row(1), matrix.column(1));
+        assert_eq!(matrix.transpose().transpose(), matrix);
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(sample().determinant(), 1.);
+        assert_eq!(Mat3::identity().determinant(), 1.);
+        assert_eq!(Mat3::scaling(&Vec3::new(2., 3., 4.)).determinant(), 24.);
+    }
+
+    #[test]
+    fn cofactor() {
+        let matrix = sample();
+
+        // The determinant of `sample` is 1, so the cofactor is exactly the inverse transpose.
+        assert_eq!(
+            matrix.cofactor().transpose(),
+            Mat3::new([[-24., 18., 5.], [20., -15., -4.], [-5., 4., 1.]])
+        );
+        assert_eq!(matrix * matrix.cofactor().transpose(), Mat3::identity());
+
+        let scaled = matrix * 2.;
+        assert_approx_eq!(
+            scaled * scaled.cofactor().transpose(),
+            Mat3::identity() * scaled.determinant()
+        );
+
+        // Singular matrices still have one.
+        let singular = Mat3::new([[1., 2., 3.], [2., 4., 6.], [0., 1., 1.]]);
+        assert_eq!(singular.determinant(), 0.);
+        assert_eq!(singular * singular.cofactor().transpose(), Mat3::identity() * 0.);
+    }
+
+    #[test]
+    fn inverse() {
+        let matrix = sample();
+
+        assert_eq!(
+            matrix.inverse().unwrap(),
+            Mat3::new([[-24., 18., 5.], [20., -15., -4.], [-5., 4., 1.]])
+        );
+
+        let scaled = matrix * 2.;
+        let inverse = scaled.inverse().unwrap();
+        assert_approx_eq!(scaled * inverse, Mat3::identity());
+        assert_approx_eq!(inverse * scaled, Mat3::identity());
+        assert_eq!(
+            Mat3::scaling(&Vec3::new(2., 4., 8.)).inverse().unwrap(),
+            Mat3::scaling(&Vec3::new(0.5, 0.25, 0.125))
+        );
+
+        let singular = Mat3::new([[1., 2., 3.], [2., 4., 6.], [0., 1., 1.]]);
+        assert!(singular.inverse().is_none());
+    }
+}
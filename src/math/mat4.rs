@@ -0,0 +1,439 @@
+#[cfg(test)]
+use crate::geometry::vec3::ApproxEq;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::math::mat3::Mat3;
+use crate::math::quaternion::Quaternion;
+
+use std::ops::Mul;
+
+// Row-major affine or projective transform, multiplies column vectors from the left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub rows: [[Float; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn new(rows: [[Float; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn identity() -> Self {
+        Self::from_linear(&Mat3::identity())
+    }
+
+    // Column by column, as glTF stores matrices.
+    pub fn from_columns(columns: [[Float; 4]; 4]) -> Self {
+        Self::new(columns).transpose()
+    }
+
+    pub fn from_linear(linear: &Mat3) -> Self {
+        let mut rows = [[0., 0., 0., 0.], [0., 0., 0., 0.], [0., 0., 0., 0.], [0., 0., 0., 1.]];
+        for (row, linear_row) in rows.iter_mut().zip(&linear.rows) {
+            row[..3].copy_from_slice(linear_row);
+        }
+
+        Self::new(rows)
+    }
+
+    pub fn translation(offset: &Vec3) -> Self {
+        let mut result = Self::identity();
+        result.rows[0][3] = offset.x;
+        result.rows[1][3] = offset.y;
+        result.rows[2][3] = offset.z;
+
+        result
+    }
+
+    pub fn scaling(scale: &Vec3) -> Self {
+        Self::from_linear(&Mat3::scaling(scale))
+    }
+
+    pub fn rotation(rotation: &Quaternion) -> Self {
+        Self::from_linear(&rotation.to_mat3())
+    }
+
+    // Scales first, then rotates and finally translates, like a glTF node.
+    pub fn from_translation_rotation_scale(
+        translation: &Vec3,
+        rotation: &Quaternion,
+        scale: &Vec3,
+    ) -> Self {
+        Self::translation(translation) * Self::rotation(rotation) * Self::scaling(scale)
+    }
+
+    // World to camera space for a camera at `eye` facing `target`, looking down -z with `up`
+    // roughly along +y.
+    pub fn look_at(eye: &Vec3, target: &Vec3, up: &Vec3) -> Self {
+        let forward = (*target - *eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(&forward);
+
+        Self::new([
+            [right.x, right.y, right.z, -right.dot_product(eye)],
+            [up.x, up.y, up.z, -up.dot_product(eye)],
+            [-forward.x, -forward.y, -forward.z, forward.dot_product(eye)],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // Camera space to OpenGL clip space, `fov` is vertical and in radians.
+    pub fn perspective(fov: Float, aspect: Float, near: Float, far: Float) -> Self {
+        let focal = 1. / (fov / 2.).tan();
+
+        Self::new([
+            [focal / aspect, 0., 0., 0.],
+            [0., focal, 0., 0.],
+            [
+                0.,
+                0.,
+                (far + near) / (near - far),
+                2. * far * near / (near - far),
+            ],
+            [0., 0., -1., 0.],
+        ])
+    }
+
+    // The upper-left 3x3 part, without translation.
+    pub fn linear(&self) -> Mat3 {
+        let mut rows = [[0.; 3]; 3];
+        for (row, own) in rows.iter_mut().zip(&self.rows) {
+            row.copy_from_slice(&own[..3]);
+        }
+
+        Mat3::new(rows)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.rows[j][i];
+            }
+        }
+
+        Self::new(rows)
+    }
+
+    // Both use the 2x2 minors of the top two and the bottom two rows.
+    fn minors(&self) -> ([Float; 6], [Float; 6]) {
+        let m = &self.rows;
+        let top = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let bottom = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+
+        (top, bottom)
+    }
+
+    pub fn determinant(&self) -> Float {
+        let (s, c) = self.minors();
+
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    // None for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        // Affine transforms, which all but projections are, only need their 3x3 part inverted.
+        if self.rows[3] == [0., 0., 0., 1.] {
+            let linear = self.linear().inverse()?;
+            let offset = linear * Vec3::new(self.rows[0][3], self.rows[1][3], self.rows[2][3]);
+
+            return Some(Self::translation(&-offset) * Self::from_linear(&linear));
+        }
+
+        let determinant = self.determinant();
+        if determinant == 0. {
+            return None;
+        }
+
+        let (s, c) = self.minors();
+        let m = &self.rows;
+        let adjugate = Self::new([
+            [
+                m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3],
+                -m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3],
+                m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3],
+                -m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3],
+            ],
+            [
+                -m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1],
+                m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1],
+                -m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1],
+                m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1],
+            ],
+            [
+                m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0],
+                -m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0],
+                m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0],
+                -m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0],
+            ],
+            [
+                -m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0],
+                m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0],
+                -m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0],
+                m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0],
+            ],
+        ]);
+
+        Some(adjugate * (1. / determinant))
+    }
+
+    // Includes the perspective divide, which is exact for affine transforms.
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        let m = &self.rows;
+        let w = Vec3::new(m[3][0], m[3][1], m[3][2]).dot_product(point) + m[3][3];
+
+        (self.linear() * *point + Vec3::new(m[0][3], m[1][3], m[2][3])) / w
+    }
+
+    pub fn transform_direction(&self, direction: &Vec3) -> Vec3 {
+        self.linear() * *direction
+    }
+
+    // Keeps normals perpendicular to transformed surfaces, also for non-uniform scales and
+    // mirroring.
+    pub fn transform_normal(&self, normal: &Vec3) -> Vec3 {
+        let linear = self.linear();
+
+        (linear.cofactor() * *normal * linear.determinant().signum()).normalize()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, matrix: Self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * matrix.rows[k][j]).sum();
+            }
+        }
+
+        Self::new(rows)
+    }
+}
+
+impl Mul<Float> for Mat4 {
+    type Output = Self;
+
+    fn mul(self, factor: Float) -> Self {
+        let mut rows = self.rows;
+        for value in rows.iter_mut().flatten() {
+            *value *= factor;
+        }
+
+        Self::new(rows)
+    }
+}
+
+#[cfg(test)]
+impl ApproxEq for Mat4 {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        self.rows
+            .iter()
+            .flatten()
+            .zip(other.rows.iter().flatten())
+            .all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::math::mat3::Mat3;
+    use crate::math::mat4::Mat4;
+    use crate::math::quaternion::Quaternion;
+
+    fn transform() -> Mat4 {
+        Mat4::from_translation_rotation_scale(
+            &Vec3::new(1., -2., 3.),
+            &Quaternion::from_axis_angle(&Vec3::new(1., 1., 0.), 0.8),
+            &Vec3::new(2., 0.5, 1.5),
+        )
+    }
+
+    #[test]
+    fn translation_rotation_scale() {
+        let point = Vec3::new(0.5, 1., -1.);
+        let rotation = Quaternion::from_axis_angle(&Vec3::new(1., 1., 0.), 0.8);
+        let expected =
+            rotation.rotate(&Vec3::new(1., 0.5, -1.5)) + Vec3::new(1., -2., 3.);
+
+        assert_approx_eq!(transform().transform_point(&point), expected);
+        assert_approx_eq!(
+            transform().transform_direction(&point),
+            expected - Vec3::new(1., -2., 3.)
+        );
+        assert_eq!(
+            Mat4::translation(&Vec3::new(1., 2., 3.)).transform_point(&Vec3::zero()),
+            Vec3::new(1., 2., 3.)
+        );
+        assert_eq!(
+            Mat4::translation(&Vec3::new(1., 2., 3.)).transform_direction(&point),
+            point
+        );
+    }
+
+    #[test]
+    fn from_columns() {
+        let matrix = Mat4::from_columns([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [4., 5., 6., 1.],
+        ]);
+
+        assert_eq!(matrix, Mat4::translation(&Vec3::new(4., 5., 6.)));
+        assert_eq!(matrix.transpose().rows[3], [4., 5., 6., 1.]);
+    }
+
+    #[test]
+    fn linear_part() {
+        let linear = Mat3::new([[1., 2., 3.], [0., 1., 4.], [5., 6., 0.]]);
+        let matrix = Mat4::translation(&Vec3::new(1., 1., 1.)) * Mat4::from_linear(&linear);
+
+        assert_eq!(matrix.linear(), linear);
+        assert_eq!(matrix.determinant(), linear.determinant());
+    }
+
+    #[test]
+    fn multiply() {
+        let a = Mat4::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 10., 11., 12.],
+            [13., 14., 15., 16.],
+        ]);
+
+        assert_eq!(a * Mat4::identity(), a);
+        assert_eq!(Mat4::identity() * a, a);
+        assert_eq!((a * a).rows[0], [90., 100., 110., 120.]);
+        assert_eq!((a * a).rows[3], [426., 484., 542., 600.]);
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(Mat4::identity().determinant(), 1.);
+        assert_eq!(Mat4::scaling(&Vec3::new(2., 3., 4.)).determinant(), 24.);
+        assert_approx_eq!(transform().determinant(), 1.5, 1e-5);
+    }
+
+    #[test]
+    fn inverse() {
+        let matrices = [
+            transform(),
+            Mat4::new([
+                [2., 0., 1., 3.],
+                [1., 1., 0., -1.],
+                [0., 4., 2., 1.],
+                [1., 0., 0., 2.],
+            ]),
+            Mat4::perspective(1., 1.5, 0.1, 100.),
+        ];
+
+        for matrix in matrices.iter() {
+            let inverse = matrix.inverse().unwrap();
+
+            assert_approx_eq!(*matrix * inverse, Mat4::identity(), 1e-4);
+            assert_approx_eq!(inverse * *matrix, Mat4::identity(), 1e-4);
+            assert_approx_eq!(
+                inverse.determinant() * matrix.determinant(),
+                1.,
+                1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn singular_has_no_inverse() {
+        let singular = Mat4::scaling(&Vec3::new(1., 0., 1.));
+
+        assert_eq!(singular.determinant(), 0.);
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn transform_normal() {
+        // Squashing a 45 degree slope makes it flatter, so its normal tilts towards +y.
+        let squash = Mat4::scaling(&Vec3::new(1., 0.5, 1.));
+        let normal = Vec3::new(-1., 1., 0.).normalize();
+        let tangent = Vec3::new(1., 1., 0.);
+
+        let transformed = squash.transform_normal(&normal);
+
+        assert_approx_eq!(
+            transformed.dot_product(&squash.transform_direction(&tangent)),
+            0.
+        );
+        assert_approx_eq!(transformed.length(), 1.);
+        assert!(transformed.y > normal.y);
+
+        // A mirror keeps the normal on the same side of the surface.
+        let mirror = Mat4::scaling(&Vec3::new(-1., 1., 1.));
+        assert_approx_eq!(
+            mirror.transform_normal(&Vec3::new(1., 0., 0.)),
+            Vec3::new(-1., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn look_at() {
+        let eye = Vec3::new(1., 2., 3.);
+        let view = Mat4::look_at(&eye, &Vec3::new(1., 2., -7.), &Vec3::new(0., 1., 0.));
+
+        assert_approx_eq!(view, Mat4::translation(&-eye));
+
+        let view = Mat4::look_at(&eye, &Vec3::new(6., 2., 3.), &Vec3::new(0., 1., 0.));
+        assert_approx_eq!(view.transform_point(&eye), Vec3::zero());
+        assert_approx_eq!(
+            view.transform_point(&Vec3::new(6., 2., 3.)),
+            Vec3::new(0., 0., -5.)
+        );
+        assert_approx_eq!(
+            view.transform_direction(&Vec3::new(0., 1., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+        assert_approx_eq!(view.linear().determinant(), 1.);
+    }
+
+    #[test]
+    fn perspective() {
+        let fov = Float::to_radians(90.);
+        let projection = Mat4::perspective(fov, 2., 1., 10.);
+
+        // The corners of the near and far planes land on the corners of the clip cube.
+        assert_approx_eq!(
+            projection.transform_point(&Vec3::new(2., 1., -1.)),
+            Vec3::new(1., 1., -1.)
+        );
+        assert_approx_eq!(
+            projection.transform_point(&Vec3::new(-20., -10., -10.)),
+            Vec3::new(-1., -1., 1.),
+            1e-4
+        );
+        assert_approx_eq!(
+            projection
+                .inverse()
+                .unwrap()
+                .transform_point(&Vec3::new(0., 0., 1.)),
+            Vec3::new(0., 0., -10.),
+            1e-4
+        );
+    }
+}
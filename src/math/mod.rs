@@ -0,0 +1,3 @@
+pub mod mat3;
+pub mod mat4;
+pub mod quaternion;
@@ -0,0 +1,291 @@
+#[cfg(test)]
+use crate::geometry::vec3::ApproxEq;
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+use crate::math::mat3::Mat3;
+
+use std::ops::{Add, Mul, Neg};
+
+// Past this dot product slerp falls back to a normalized lerp, which avoids dividing by the
+// sine of a tiny angle.
+const SLERP_LINEAR_THRESHOLD: Float = 0.9995;
+
+// Rotation as a unit quaternion, components in glTF order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
+    pub w: Float,
+}
+
+impl Quaternion {
+    pub const fn new(x: Float, y: Float, z: Float, w: Float) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new(0., 0., 0., 1.)
+    }
+
+    // Counter-clockwise by `angle` radians when looking against `axis`.
+    pub fn from_axis_angle(axis: &Vec3, angle: Float) -> Self {
+        let axis = axis.normalize() * (angle / 2.).sin();
+
+        Self::new(axis.x, axis.y, axis.z, (angle / 2.).cos())
+    }
+
+    // Expects a pure rotation. Starts from the largest diagonal term to stay accurate for
+    // rotations close to 180 degrees.
+    pub fn from_mat3(matrix: &Mat3) -> Self {
+        let m = &matrix.rows;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                s / 4.,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1. + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.;
+            Self::new(
+                s / 4.,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1. + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.;
+            Self::new(
+                (m[0][1] + m[1][0]) / s,
+                s / 4.,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = (1. + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.;
+            Self::new(
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.,
+                (m[1][0] - m[0][1]) / s,
+            )
+        }
+    }
+
+    pub fn to_mat3(self) -> Mat3 {
+        let Self { x, y, z, w } = self;
+
+        Mat3::new([
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - w * z),
+                2. * (x * z + w * y),
+            ],
+            [
+                2. * (x * y + w * z),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - w * x),
+            ],
+            [
+                2. * (x * z - w * y),
+                2. * (y * z + w * x),
+                1. - 2. * (x * x + y * y),
+            ],
+        ])
+    }
+
+    pub fn vector(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn dot(&self, other: &Self) -> Float {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(&self) -> Float {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        *self * (1. / self.length())
+    }
+
+    pub fn rotate(&self, vec: &Vec3) -> Vec3 {
+        let axis = self.vector();
+        let t = axis.cross(vec) * 2.;
+
+        *vec + t * self.w + axis.cross(&t)
+    }
+
+    // Constant angular speed from `self` at 0 to `other` at 1, along the shorter arc.
+    pub fn slerp(&self, other: &Self, t: Float) -> Self {
+        let mut cos = self.dot(other);
+        let mut other = *other;
+
+        if cos < 0. {
+            cos = -cos;
+            other = -other;
+        }
+
+        if cos > SLERP_LINEAR_THRESHOLD {
+            return (*self * (1. - t) + other * t).normalize();
+        }
+
+        let angle = cos.acos();
+        let sin = angle.sin();
+
+        *self * (((1. - t) * angle).sin() / sin) + other * ((t * angle).sin() / sin)
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+
+    // Rotates by `quaternion` first, then by `self`.
+    fn mul(self, quaternion: Self) -> Self {
+        let (a, b) = (self.vector(), quaternion.vector());
+        let vector = b * self.w + a * quaternion.w + a.cross(&b);
+
+        Self::new(
+            vector.x,
+            vector.y,
+            vector.z,
+            self.w * quaternion.w - a.dot_product(&b),
+        )
+    }
+}
+
+impl Mul<Float> for Quaternion {
+    type Output = Self;
+
+    fn mul(self, factor: Float) -> Self {
+        Self::new(
+            self.x * factor,
+            self.y * factor,
+            self.z * factor,
+            self.w * factor,
+        )
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Self;
+
+    fn add(self, quaternion: Self) -> Self {
+        Self::new(
+            self.x + quaternion.x,
+            self.y + quaternion.y,
+            self.z + quaternion.z,
+            self.w + quaternion.w,
+        )
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+// Equal rotations, so `q` and `-q` compare equal.
+#[cfg(test)]
+impl ApproxEq for Quaternion {
+    fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        (1. - self.dot(other).abs()).abs() <= epsilon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::math::mat3::Mat3;
+    use crate::math::quaternion::Quaternion;
+
+    fn quarter_turn_y() -> Quaternion {
+        Quaternion::from_axis_angle(&Vec3::new(0., 1., 0.), Float::to_radians(90.))
+    }
+
+    #[test]
+    fn rotate() {
+        let x = Vec3::new(1., 0., 0.);
+
+        assert_approx_eq!(quarter_turn_y().rotate(&x), Vec3::new(0., 0., -1.));
+        assert_eq!(Quaternion::identity().rotate(&x), x);
+    }
+
+    #[test]
+    fn multiply_composes_rotations() {
+        let roll = Quaternion::from_axis_angle(&Vec3::new(0., 0., 1.), 0.7);
+        let turn = quarter_turn_y() * roll;
+        let vec = Vec3::new(0.3, -2., 1.5);
+
+        assert_approx_eq!(
+            turn.rotate(&vec),
+            quarter_turn_y().rotate(&roll.rotate(&vec))
+        );
+        assert_eq!(turn * Quaternion::identity(), turn);
+        assert_approx_eq!(quarter_turn_y() * quarter_turn_y() * roll, quarter_turn_y() * turn);
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let vec = Vec3::new(0.3, -2., 1.5);
+        let rotations = [
+            Quaternion::identity(),
+            quarter_turn_y(),
+            Quaternion::from_axis_angle(&Vec3::new(1., 2., 3.), 2.5),
+            // Half turns exercise every branch of `from_mat3`.
+            Quaternion::from_axis_angle(&Vec3::new(1., 0., 0.), Float::to_radians(180.)),
+            Quaternion::from_axis_angle(&Vec3::new(0., 1., 0.), Float::to_radians(180.)),
+            Quaternion::from_axis_angle(&Vec3::new(0., 0., 1.), Float::to_radians(180.)),
+        ];
+
+        for rotation in rotations.iter() {
+            let matrix = rotation.to_mat3();
+
+            assert_approx_eq!(matrix * vec, rotation.rotate(&vec), 1e-4);
+            assert_approx_eq!(matrix.determinant(), 1., 1e-5);
+            assert_approx_eq!(matrix * matrix.transpose(), Mat3::identity());
+            assert_approx_eq!(Quaternion::from_mat3(&matrix), *rotation);
+        }
+        assert_eq!(
+            Quaternion::from_mat3(&Mat3::identity()),
+            Quaternion::identity()
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        let scaled = quarter_turn_y() * 3.;
+
+        assert_approx_eq!(scaled.length(), 3.);
+        assert_approx_eq!(scaled.normalize().length(), 1.);
+        assert_approx_eq!(scaled.normalize(), quarter_turn_y());
+    }
+
+    #[test]
+    fn slerp() {
+        let start = Quaternion::identity();
+        let end = quarter_turn_y();
+        let x = Vec3::new(1., 0., 0.);
+        let eighth = Float::to_radians(45.);
+
+        assert_approx_eq!(start.slerp(&end, 0.), start);
+        assert_approx_eq!(start.slerp(&end, 1.), end);
+        assert_approx_eq!(
+            start.slerp(&end, 0.5).rotate(&x),
+            Vec3::new(eighth.cos(), 0., -eighth.sin())
+        );
+        assert_approx_eq!(start.slerp(&end, 0.25).length(), 1.);
+        // `-end` is the same rotation, the interpolation still takes the short way.
+        assert_approx_eq!(start.slerp(&-end, 0.5), start.slerp(&end, 0.5));
+        assert_eq!(start.slerp(&start, 0.3), start);
+    }
+}
@@ -1,15 +1,38 @@
+use crate::geometry::consts::PI;
+use crate::geometry::Float;
+use crate::geometry::material::lambertian::Lambertian;
+use crate::geometry::material::{Material, Surface};
+use crate::geometry::object::{Hit, Object};
 use crate::geometry::ray::Ray;
-use crate::geometry::sphere::Sphere;
 use crate::geometry::vec3::Vec3;
-use crate::scene::Scene;
+use crate::math::mat4::Mat4;
+use crate::scene::{Integrator, Scene};
 use crate::utils;
+use crate::utils::random::Random;
 use crate::utils::rgb::RGB;
+use crate::utils::spectrum;
 
 use image::{ImageBuffer, Rgb};
 use std::sync::mpsc::channel;
 use std::sync::Arc;
 use threadpool::ThreadPool;
 
+// Side of the pixel blocks whose primary rays are traced together as one packet.
+const PACKET_SIZE: usize = 4;
+// Points picked on glowing objects for every shaded point. Their soft shadows stay noisy unless
+// several samples are taken per pixel.
+const EMITTER_SAMPLES: usize = 4;
+// Share of the way to a point picked on an emitter that shadow rays check, so that the emitter
+// doesn't shadow itself.
+const SHADOW_REACH: Float = 1. - 1e-3;
+// Random walks through the medium of translucent materials for every shaded point, and the
+// scattering events followed along each before giving up on it.
+const SUBSURFACE_WALKS: usize = 8;
+const SUBSURFACE_STEPS: usize = 64;
+// Cut out surfaces a ray passes through before whatever it hits next is taken as solid, bounds
+// the cost of dense foliage.
+const MAX_CUTOUTS: usize = 64;
+
 pub fn render_frame(scene: &Arc<Scene>, pool: &ThreadPool) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
     let (width, height) = (scene.canvas.width, scene.canvas.height);
     let cpus_count = num_cpus::get();
@@ -26,15 +49,34 @@ pub fn render_frame(scene: &Arc<Scene>, pool: &ThreadPool) -> ImageBuffer<Rgb<u8
         let end = start + chunk_length;
 
         pool.execute(move || {
-            for y in start..end {
-                let mut row = Vec::with_capacity(scene.canvas.width);
-
-                for x in 0..width {
-                    let color = render_pixel(x as f32, y as f32, &scene);
-                    row.push(color);
+            if scene.options.packet_tracing {
+                for block_y in (start..end).step_by(PACKET_SIZE) {
+                    let block_height = PACKET_SIZE.min(end - block_y);
+                    let mut rows = vec![Vec::with_capacity(width); block_height];
+
+                    for block_x in (0..width).step_by(PACKET_SIZE) {
+                        let block_width = PACKET_SIZE.min(width - block_x);
+                        let colors =
+                            render_block(block_x, block_y, block_width, block_height, &scene);
+
+                        for (row, colors) in rows.iter_mut().zip(colors.chunks(block_width)) {
+                            row.extend_from_slice(colors);
+                        }
+                    }
+
+                    chunk.extend(rows);
                 }
+            } else {
+                for y in start..end {
+                    let mut row = Vec::with_capacity(scene.canvas.width);
+
+                    for x in 0..width {
+                        let color = render_pixel(x as Float, y as Float, &scene);
+                        row.push(color);
+                    }
 
-                chunk.push(row);
+                    chunk.push(row);
+                }
             }
 
             tx.send((worker_num, chunk))
@@ -60,134 +102,852 @@ pub fn render_frame(scene: &Arc<Scene>, pool: &ThreadPool) -> ImageBuffer<Rgb<u8
     buffer
 }
 
-pub fn render_pixel(x: f32, y: f32, scene: &Scene) -> RGB {
-    let direction = Vec3::new(
-        (x + 0.5) - scene.canvas.width as f32 / 2.,
-        -(y + 0.5) + scene.canvas.height as f32 / 2.,
-        -(scene.canvas.height as f32) / (2. * (scene.canvas.fov / 2.).tan()), // ).scale_by_matrix(scene.camera.rotation_matrix).normalize();
-    )
-    .normalize();
+pub fn render_pixel(x: Float, y: Float, scene: &Scene) -> RGB {
+    let samples = scene.options.samples_per_pixel.max(1);
+
+    let unprojection = unprojection(scene);
+
+    if samples == 1 {
+        return render_sample(x, y, 0, 0., &unprojection, scene);
+    }
+
+    let sum = (0..samples).fold(Vec3::zero(), |sum, sample| {
+        let time = sample_time(x, y, sample, samples);
+        sum + render_sample(x, y, sample, time, &unprojection, scene).as_vector()
+    });
+
+    RGB::from_vector(&(sum / samples as Float))
+}
+
+// Renders a block of pixels row by row, tracing the primary rays of each sample as a packet.
+// Produces the same colors as `render_pixel` for every pixel of the block.
+pub fn render_block(x: usize, y: usize, width: usize, height: usize, scene: &Scene) -> Vec<RGB> {
+    let samples = scene.options.samples_per_pixel.max(1);
+    let pixels: Vec<(Float, Float)> = (y..y + height)
+        .flat_map(|y| (x..x + width).map(move |x| (x as Float, y as Float)))
+        .collect();
+    let unprojection = unprojection(scene);
+
+    let randoms = |sample| {
+        pixels
+            .iter()
+            .map(|&(x, y)| Random::new(sample_hash(x, y, sample)))
+            .collect::<Vec<Random>>()
+    };
+
+    if samples == 1 {
+        let mut rays: Vec<Ray> = pixels
+            .iter()
+            .map(|&(x, y)| primary_ray(x, y, 0., &unprojection, scene))
+            .collect();
+
+        return cast_packet(&mut rays, &mut randoms(0), scene);
+    }
+
+    let mut sums = vec![Vec3::zero(); pixels.len()];
+
+    for sample in 0..samples {
+        let mut rays: Vec<Ray> = pixels
+            .iter()
+            .map(|&(x, y)| {
+                let time = sample_time(x, y, sample, samples);
+                primary_ray(x, y, time, &unprojection, scene)
+            })
+            .collect();
+        let colors = cast_packet(&mut rays, &mut randoms(sample), scene);
 
-    let origin = Vec3::new(
-        scene.camera.position.x,
-        scene.camera.position.y,
-        scene.camera.position.z,
+        for (sum, color) in sums.iter_mut().zip(colors) {
+            *sum += color.as_vector();
+        }
+    }
+
+    sums.iter()
+        .map(|sum| RGB::from_vector(&(*sum / samples as Float)))
+        .collect()
+}
+
+fn render_sample(
+    x: Float,
+    y: Float,
+    sample: usize,
+    time: Float,
+    unprojection: &Mat4,
+    scene: &Scene,
+) -> RGB {
+    let mut ray = primary_ray(x, y, time, unprojection, scene);
+    let closest = closest_hit(&mut ray, scene);
+    let mut random = Random::new(sample_hash(x, y, sample));
+
+    shade_primary(&ray, closest, scene, &mut random)
+}
+
+// Clip space back to camera space. Only directions matter, so the planes are arbitrary.
+fn unprojection(scene: &Scene) -> Mat4 {
+    let aspect = scene.canvas.width as Float / scene.canvas.height as Float;
+
+    Mat4::perspective(scene.canvas.fov, aspect, 1., 100.)
+        .inverse()
+        .expect("Field of view must be between 0 and 180 degrees")
+}
+
+fn primary_ray(x: Float, y: Float, time: Float, unprojection: &Mat4, scene: &Scene) -> Ray {
+    let clip = Vec3::new(
+        2. * (x + 0.5) / scene.canvas.width as Float - 1.,
+        1. - 2. * (y + 0.5) / scene.canvas.height as Float,
+        -1.,
     );
-    let mut ray = Ray::new(origin, direction, std::f32::MAX);
+    let direction = unprojection.transform_point(&clip);
+    let direction = scene.camera.orientation_at(time).rotate(&direction).normalize();
+
+    let origin = scene.camera.position_at(time);
+    // Angle covered by a pixel, the cone grows by about that much per unit of distance.
+    let spread = 2. * (scene.canvas.fov / 2.).tan() / scene.canvas.height as Float;
+
+    Ray::new(origin, direction, Float::MAX)
+        .at_time(time)
+        .with_cone(0., spread)
+}
+
+// Stratified over the shutter, jittered per pixel so the strata don't show up as ghost copies.
+fn sample_time(x: Float, y: Float, sample: usize, samples: usize) -> Float {
+    let jitter = (sample_hash(x, y, sample) & 0xffff) as Float / 65_536.;
+
+    (sample as Float + jitter) / samples as Float
+}
+
+fn sample_hash(x: Float, y: Float, sample: usize) -> u64 {
+    let mut hash = (x as u32).wrapping_mul(73_856_093)
+        ^ (y as u32).wrapping_mul(19_349_663)
+        ^ (sample as u32).wrapping_mul(83_492_791);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^= hash >> 15;
+
+    hash as u64
+}
 
-    let pixel = cast_ray(&mut ray, scene, 0);
+fn closest_hit<'a>(ray: &mut Ray, scene: &'a Scene) -> Option<(Hit, &'a dyn Object)> {
+    let reach = ray.t;
+    let closest = nearest_hit(ray, scene);
 
-    return pixel;
+    past_cutouts(ray, reach, closest, scene)
 }
 
-fn cast_ray(ray: &mut Ray, scene: &Scene, depth: usize) -> RGB {
+fn nearest_hit<'a>(ray: &mut Ray, scene: &'a Scene) -> Option<(Hit, &'a dyn Object)> {
+    let mut closest = None;
+
+    for object in &scene.objects {
+        if let Some(hit) = object.intersect(ray) {
+            closest = Some((hit, object.as_ref()));
+        }
+    }
+
+    closest
+}
+
+// Carries on from `closest` through the cut out parts of surfaces, up to `reach` along the ray,
+// which ends up shortened to the first solid hit.
+fn past_cutouts<'a>(
+    ray: &mut Ray,
+    reach: Float,
+    closest: Option<(Hit, &'a dyn Object)>,
+    scene: &'a Scene,
+) -> Option<(Hit, &'a dyn Object)> {
+    let (mut hit, mut object) = closest?;
+    let mut rest = *ray;
+
+    for _ in 0..MAX_CUTOUTS {
+        if !cut_out(&rest, &hit, object.material(&hit)) {
+            break;
+        }
+
+        rest.origin = utils::move_from_surface(&rest, &ray.direction, &hit.normal, &hit.point);
+        let travelled = (rest.origin - ray.origin).dot_product(&ray.direction);
+        rest.t = reach - travelled;
+
+        let (next, next_object) = nearest_hit(&mut rest, scene)?;
+        ray.t = travelled + rest.t;
+        hit = next;
+        object = next_object;
+    }
+
+    Some((hit, object))
+}
+
+fn cast_ray(ray: &mut Ray, scene: &Scene, depth: usize, random: &mut Random) -> RGB {
     if depth > scene.options.reflections_limit {
-        return scene.options.background_color.clone();
+        return scene.options.background_color;
     }
 
-    let mut pairs: Vec<(f32, usize)> = scene
-        .objects
-        .iter()
-        .enumerate()
-        .map(|pair| (pair.1.center.minus(&ray.origin).length(), pair.0))
-        .collect();
-    pairs.sort_by(|pair1, pair2| pair1.0.partial_cmp(&pair2.0).unwrap());
+    match closest_hit(ray, scene) {
+        Some((hit, object)) => {
+            get_pixel_color(ray, &hit, object.material(&hit), scene, depth, random)
+        }
+        None => scene.options.background_color,
+    }
+}
 
-    let order: Vec<usize> = pairs.iter().map(|pair| pair.1).collect();
+fn shade_primary(
+    ray: &Ray,
+    closest: Option<(Hit, &dyn Object)>,
+    scene: &Scene,
+    random: &mut Random,
+) -> RGB {
+    let (hit, object) = match closest {
+        Some(closest) => closest,
+        None => return scene.options.background_color,
+    };
+    let material = object.material(&hit);
 
-    for index in order {
-        let sphere = &scene.objects.get(index).unwrap();
+    match scene.options.integrator {
+        Integrator::Whitted => get_pixel_color(ray, &hit, material, scene, 0, random),
+        Integrator::PathTracing => {
+            RGB::from_vector(&(trace_path(ray, hit, material, scene, random) * 255.))
+        }
+    }
+}
+
+// Primary rays only: every packet member is shaded on its own, so secondary bounces go
+// through `cast_ray` one ray at a time.
+fn cast_packet(rays: &mut [Ray], randoms: &mut [Random], scene: &Scene) -> Vec<RGB> {
+    let mut closest: Vec<Option<(Hit, &dyn Object)>> = rays.iter().map(|_| None).collect();
 
-        if sphere.ray_intersect(ray) {
-            return get_pixel_color(ray, sphere, scene, depth);
+    for object in &scene.objects {
+        for (closest, hit) in closest.iter_mut().zip(object.intersect_packet(rays)) {
+            if let Some(hit) = hit {
+                *closest = Some((hit, object.as_ref()));
+            }
         }
     }
 
-    scene.options.background_color.clone()
+    closest
+        .into_iter()
+        .zip(rays.iter_mut())
+        .zip(randoms.iter_mut())
+        .map(|((closest, ray), random)| {
+            let closest = past_cutouts(ray, Float::MAX, closest, scene);
+            shade_primary(ray, closest, scene, random)
+        })
+        .collect()
 }
 
 fn scene_intersects(ray: &mut Ray, scene: &Scene) -> bool {
-    for sphere in &scene.objects {
-        if sphere.ray_intersect(ray) {
-            return true;
+    let reach = ray.t;
+
+    for object in &scene.objects {
+        if let Some(hit) = object.intersect(ray) {
+            if !cut_out(ray, &hit, object.material(&hit)) {
+                return true;
+            }
+
+            // Something else may still block the ray, closer or further along.
+            ray.t = reach;
+            return closest_hit(ray, scene).is_some();
         }
     }
 
     false
 }
 
-fn get_pixel_color(ray: &Ray, sphere: &Sphere, scene: &Scene, depth: usize) -> RGB {
-    let hit_point = ray.origin.plus(&ray.direction.scale(ray.t));
-    let hit_normal = hit_point.minus(&sphere.center).normalize();
-
-    let reflect_direction = reflect(&ray.direction, &hit_normal).normalize();
-    let refract_direction = refract(
-        &ray.direction,
-        &hit_normal,
-        sphere.material.refractive_index,
-        1.,
-    )
-    .normalize();
-    let reflect_origin = utils::move_from_surface(&reflect_direction, &hit_normal, &hit_point);
-    let refract_origin = utils::move_from_surface(&refract_direction, &hit_normal, &hit_point);
-    let mut reflected_ray = Ray::new(reflect_origin, reflect_direction, std::f32::MAX);
-    let mut refracted_ray = Ray::new(refract_origin, refract_direction, std::f32::MAX);
-
-    let reflect_color = cast_ray(&mut reflected_ray, scene, depth + 1);
-    let refract_color = cast_ray(&mut refracted_ray, scene, depth + 1);
-
-    let mut diffuse_light_intensity = 0.;
-    let mut specular_light_intensity = 0.;
-
-    for light in &scene.lights {
-        let light_direction = light.position.minus(&hit_point).normalize();
-        let shadow_origin = utils::move_from_surface(&light_direction, &hit_normal, &hit_point);
-        let mut bounced_light_ray = Ray::new(shadow_origin, light_direction.clone(), std::f32::MAX);
-
-        if scene_intersects(&mut bounced_light_ray, scene) {
-            continue;
+fn get_pixel_color(
+    ray: &Ray,
+    hit: &Hit,
+    material: &dyn Material,
+    scene: &Scene,
+    depth: usize,
+    random: &mut Random,
+) -> RGB {
+    // Dispersive materials send every band of white light their own way, each is traced on
+    // its own at a random wavelength within the band.
+    if material.dispersive() && ray.wavelength.is_none() {
+        let pixel = (0..spectrum::BANDS).fold(Vec3::zero(), |sum, band| {
+            let wavelength = spectrum::wavelength(band, random.uniform());
+            let ray = ray.with_wavelength(Some(wavelength));
+            let color = get_pixel_color(&ray, hit, material, scene, depth, random);
+            sum + color.as_vector() / 255. * spectrum::color(band)
+        });
+
+        return RGB::from_vector(&(pixel * 255.));
+    }
+
+    let surface = surface(ray, hit, material);
+    let to_viewer = -ray.direction;
+
+    let mut pixel = material.emission() + direct_light(ray, hit, &surface, material, scene, random);
+
+    for lobe in material.specular(&surface, &to_viewer) {
+        let mut secondary = secondary_ray(ray, hit, &lobe.direction);
+        let reflected = cast_ray(&mut secondary, scene, depth + 1, random);
+        pixel += reflected.as_vector() / 255. * lobe.weight;
+    }
+
+    RGB::from_vector(&(pixel * 255.))
+}
+
+// Lights every vertex of the path directly, then continues it in a direction sampled from the
+// material. Paths that escape the scene pick up the background. Glowing objects reached after
+// scattering were already sampled as emitters, only specular bounces pick up their glow. At the
+// first dispersive material the path picks one band of the spectrum to carry on with.
+fn trace_path(
+    ray: &Ray,
+    hit: Hit,
+    material: &dyn Material,
+    scene: &Scene,
+    random: &mut Random,
+) -> Vec3 {
+    let background = scene.options.background_color.as_vector() / 255.;
+    let (mut ray, mut hit, mut material) = (*ray, hit, material);
+    let mut color = Vec3::zero();
+    let mut throughput = Vec3::new(1., 1., 1.);
+    let mut specular = true;
+
+    for _ in 0..=scene.options.reflections_limit {
+        if material.dispersive() && ray.wavelength.is_none() {
+            let band = ((random.uniform() * spectrum::BANDS as Float) as usize)
+                .min(spectrum::BANDS - 1);
+            ray = ray.with_wavelength(Some(spectrum::wavelength(band, random.uniform())));
+            throughput = throughput * spectrum::color(band) * spectrum::BANDS as Float;
+        }
+
+        let surface = surface(&ray, &hit, material);
+        if specular {
+            color += throughput * material.emission();
+        }
+        color += throughput * direct_light(&ray, &hit, &surface, material, scene, random);
+
+        let to_viewer = -ray.direction;
+        let sample = match material.sample(&surface, &to_viewer, random.pair()) {
+            Some(sample) => sample,
+            None => break,
         };
+        specular = material.pdf(&surface, &to_viewer, &sample.direction) <= 0.;
+        throughput = throughput * sample.weight;
+        ray = secondary_ray(&ray, &hit, &sample.direction);
+
+        match closest_hit(&mut ray, scene) {
+            Some((next, object)) => {
+                material = object.material(&next);
+                hit = next;
+            }
+            None => return color + throughput * background,
+        }
+    }
+
+    color
+}
+
+// `ray` has been shortened to the hit.
+fn surface(ray: &Ray, hit: &Hit, material: &dyn Material) -> Surface {
+    let mut surface = plain_surface(ray, hit, material);
 
-        diffuse_light_intensity +=
-            light.intensity * f32::max(0., light_direction.dot_product(&hit_normal));
-        specular_light_intensity += f32::max(
-            0.,
-            reflect(&light_direction, &hit_normal).dot_product(&ray.direction),
-        )
-        .powf(sphere.material.specular_exponent)
-            * light.intensity;
+    if hit.color.is_none() {
+        if let Some(color) = material.texture_color(&surface) {
+            surface.color = color;
+        }
     }
+    if let Some(normal) = material.shading_normal(&surface) {
+        surface.normal = normal;
+    }
+
+    surface
+}
 
-    let pixel = sphere
-        .material
-        .color
-        .as_vector()
-        .scale(diffuse_light_intensity * sphere.material.albedo.0)
-        .plus(
-            &Vec3::new(255., 255., 255.).scale(specular_light_intensity * sphere.material.albedo.1),
-        )
-        .plus(&reflect_color.as_vector().scale(sphere.material.albedo.2))
-        .plus(&refract_color.as_vector().scale(sphere.material.albedo.3));
+// The surface before textures and normal maps. At grazing angles the ray covers a longer stretch
+// of it, up to a limit that keeps textures from blurring out entirely at the horizon.
+fn plain_surface(ray: &Ray, hit: &Hit, material: &dyn Material) -> Surface {
+    let cos = hit.normal.dot_product(&ray.direction).abs().max(0.1);
+
+    Surface {
+        normal: hit.normal,
+        tangent: hit.tangent,
+        bitangent: hit.bitangent,
+        color: hit.color.unwrap_or_else(|| material.color()).as_vector() / 255.,
+        uv: hit.uv,
+        footprint: ray.footprint() / cos,
+        point: hit.point,
+        local_point: hit.local_point,
+        wavelength: ray.wavelength,
+    }
+}
 
-    RGB::from_vector(&pixel)
+fn cut_out(ray: &Ray, hit: &Hit, material: &dyn Material) -> bool {
+    material.cut_out(&plain_surface(ray, hit, material))
 }
 
-fn reflect(light: &Vec3, normal: &Vec3) -> Vec3 {
-    light.minus(&normal.scale(2. * light.dot_product(normal)))
+// Light intensities are scaled by pi, so a light of intensity 1 facing a white matte surface
+// brings it to full brightness.
+fn direct_light(
+    ray: &Ray,
+    hit: &Hit,
+    surface: &Surface,
+    material: &dyn Material,
+    scene: &Scene,
+    random: &mut Random,
+) -> Vec3 {
+    let to_viewer = -ray.direction;
+
+    let lights = visible_lights(ray, hit, scene, random).fold(
+        Vec3::zero(),
+        |sum, (light_direction, radiance)| {
+            sum + material.evaluate(surface, &to_viewer, &light_direction) * radiance * PI
+        },
+    );
+
+    lights
+        + emitted_light(ray, hit, surface, material, scene, random)
+        + subsurface_light(ray, hit, material, scene, random)
 }
 
-fn refract(light: &Vec3, normal: &Vec3, eta_t: f32, eta_i: f32) -> Vec3 {
-    let cosi = -f32::max(-1., f32::min(1., light.dot_product(&normal)));
-    if cosi < 0. {
-        return refract(light, &normal.scale(-1.), eta_i, eta_t);
+// Light from glowing objects, estimated from a few points picked on them. Surrounded by a glow
+// of 1, a white matte surface is at full brightness like under a light of intensity 1.
+fn emitted_light(
+    ray: &Ray,
+    hit: &Hit,
+    surface: &Surface,
+    material: &dyn Material,
+    scene: &Scene,
+    random: &mut Random,
+) -> Vec3 {
+    if scene.emitters.is_empty() {
+        return Vec3::zero();
+    }
+    let to_viewer = -ray.direction;
+
+    let sum = (0..EMITTER_SAMPLES).fold(Vec3::zero(), |sum, _| {
+        let (emitter, probability) = match scene.emitters.pick(random.uniform()) {
+            Some(picked) => picked,
+            None => return sum,
+        };
+        let sample = match emitter.sample(&hit.point, ray.time, random.pair()) {
+            Some(sample) => sample,
+            None => return sum,
+        };
+
+        let origin = utils::move_from_surface(ray, &sample.direction, &hit.normal, &hit.point);
+        let mut shadow_ray =
+            Ray::new(origin, sample.direction, sample.distance * SHADOW_REACH).at_time(ray.time);
+        if scene_intersects(&mut shadow_ray, scene) {
+            return sum;
+        }
+
+        sum + material.evaluate(surface, &to_viewer, &sample.direction) * emitter.radiance
+            / (sample.pdf * probability)
+    });
+
+    sum / EMITTER_SAMPLES as Float
+}
+
+// Light entering translucent materials elsewhere and scattering through them to the hit,
+// estimated with random walks from the hit into the medium. Where a walk comes out, the surface
+// is lit directly as if it were white and matte. Walks are tracked in one color channel at a
+// time and weighted by all three, so colors bleed by how far each channel gets.
+fn subsurface_light(
+    ray: &Ray,
+    hit: &Hit,
+    material: &dyn Material,
+    scene: &Scene,
+    random: &mut Random,
+) -> Vec3 {
+    let medium = match material.subsurface() {
+        Some(medium) => medium,
+        None => return Vec3::zero(),
+    };
+    let average = |color: Vec3| (color.x + color.y + color.z) / 3.;
+    let ior = material.ior();
+    let reflectance = ((ior - 1.) / (ior + 1.)).powi(2);
+    let cos = hit.normal.dot_product(&ray.direction).abs();
+    let entering = 1. - (reflectance + (1. - reflectance) * (1. - cos).powi(5));
+    let white = Lambertian {
+        color: RGB::new(255, 255, 255),
+        color_texture: None,
     };
 
-    let eta = eta_i / eta_t;
-    let k = 1. - eta.powi(2) * (1. - cosi.powi(2));
+    let mut sum = Vec3::zero();
+    for _ in 0..SUBSURFACE_WALKS {
+        let mut direction = ray.direction.refract(&hit.normal, ior, 1.).normalize();
+        let mut point = utils::move_from_surface(ray, &direction, &hit.normal, &hit.point);
+        let mut throughput = Vec3::new(1., 1., 1.);
+        let channel = ((random.uniform() * 3.) as usize).min(2);
+
+        for _ in 0..SUBSURFACE_STEPS {
+            let distance = medium.free_path(channel, random.uniform());
+            let mut inside = Ray::new(point, direction, Float::MAX).at_time(ray.time);
+            let mut exit = match closest_hit(&mut inside, scene) {
+                Some((exit, _)) => exit,
+                None => break,
+            };
+
+            if inside.t <= distance {
+                let transmittance = medium.transmittance(inside.t);
+                throughput = throughput * transmittance / average(transmittance);
+
+                if exit.normal.dot_product(&direction) < 0. {
+                    exit.normal = -exit.normal;
+                }
+                let outside = Ray::new(exit.point, -exit.normal, 0.).at_time(ray.time);
+                let surface = surface(&outside, &exit, &white);
+                let light = direct_light(&outside, &exit, &surface, &white, scene, random);
+                sum += throughput * light * (1. - reflectance);
+                break;
+            }
 
-    if k < 0. {
-        Vec3::new(1., 0., 0.)
-    } else {
-        light.scale(eta).plus(&normal.scale(eta * cosi - k.sqrt()))
+            let transmittance = medium.transmittance(distance);
+            throughput = throughput * medium.scattering * transmittance
+                / average(medium.extinction() * transmittance);
+            point += direction * distance;
+            direction = medium.direction(random.pair());
+        }
+    }
+
+    sum * entering / SUBSURFACE_WALKS as Float
+}
+
+// Leaves the hit point towards `direction`, e.g. a reflection. The cone carries on from its width
+// at the hit, as if the surface were flat.
+fn secondary_ray(ray: &Ray, hit: &Hit, direction: &Vec3) -> Ray {
+    let origin = utils::move_from_surface(ray, direction, &hit.normal, &hit.point);
+
+    Ray::new(origin, *direction, Float::MAX)
+        .at_time(ray.time)
+        .with_cone(ray.footprint(), ray.spread)
+        .with_wavelength(ray.wavelength)
+}
+
+// Lights not shadowed at the hit point, with the direction towards each of them and the light
+// arriving from it. Lights that aren't points are seen from a random point of theirs, so their
+// shadows are soft. Whatever is beyond a light doesn't shadow it.
+fn visible_lights<'a>(
+    ray: &'a Ray,
+    hit: &'a Hit,
+    scene: &'a Scene,
+    random: &'a mut Random,
+) -> impl Iterator<Item = (Vec3, Vec3)> + 'a {
+    scene.lights.iter().filter_map(move |light| {
+        let (light_direction, distance) = light.direction_from(&hit.point, random.pair());
+        let radiance = light.radiance(&light_direction, distance);
+        if radiance == Vec3::zero() {
+            return None;
+        }
+        let shadow_origin =
+            utils::move_from_surface(ray, &light_direction, &hit.normal, &hit.point);
+        let mut shadow_ray =
+            Ray::new(shadow_origin, light_direction, distance.min(Float::MAX)).at_time(ray.time);
+
+        if scene_intersects(&mut shadow_ray, scene) {
+            None
+        } else {
+            Some((light_direction, radiance))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::consts::PI;
+    use crate::geometry::material::cutout::Cutout;
+    use crate::geometry::material::emissive::Emissive;
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::subsurface::{Medium, Translucent};
+    use crate::geometry::material::{Material, Sample, Surface};
+    use crate::geometry::motion::Moving;
+    use crate::geometry::object::Object;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::sphere_set::SphereSet;
+    use crate::geometry::vec3::Vec3;
+    use crate::geometry::Float;
+    use crate::loaders::materials::MaterialLibrary;
+    use crate::renderer::{render_block, render_pixel};
+    use crate::scene::camera::Camera;
+    use crate::scene::light::{Attenuation, Emitters, Light};
+    use crate::scene::{Canvas, Integrator, Scene, SceneOptions};
+    use crate::utils::rgb::RGB;
+
+    fn scene(samples_per_pixel: usize, integrator: Integrator) -> Scene {
+        let materials = MaterialLibrary::builtin();
+        let mut spheres = SphereSet::new();
+        spheres.push(Sphere::new(
+            Vec3::new(-3., 0., -16.),
+            2.,
+            materials.get("dark_green_plastic").unwrap(),
+        ));
+        spheres.push(Sphere::new(
+            Vec3::new(-1., -1.5, -12.),
+            2.,
+            materials.get("glass").unwrap(),
+        ));
+        spheres.push(Sphere::new(
+            Vec3::new(7., 5., -18.),
+            4.,
+            materials.get("mirror").unwrap(),
+        ));
+        spheres.push(Sphere::new(Vec3::new(-6., 4., -14.), 1., glowing(2.)));
+        let moving = Moving::new(
+            Box::new(Sphere::new(
+                Vec3::new(1.5, -0.5, -18.),
+                3.,
+                materials.get("red_rubber").unwrap(),
+            )),
+            Vec3::new(1., 0., 0.),
+        );
+
+        let mut camera = Camera::new(Vec3::zero());
+        camera.displacement = Vec3::new(0., 0.2, 0.);
+
+        let objects: Vec<Box<dyn Object>> = vec![Box::new(spheres), Box::new(moving)];
+
+        Scene {
+            emitters: Emitters::new(&objects),
+            objects,
+            lights: vec![
+                Light::new(Vec3::new(-20., 20., 20.), 1.5),
+                Light::new(Vec3::new(30., 20., -25.), 1.8),
+            ],
+            camera,
+            canvas: Canvas {
+                width: 30,
+                height: 22,
+                fov: 1.,
+            },
+            options: SceneOptions {
+                reflections_limit: 4,
+                background_color: RGB::new(178, 178, 178),
+                samples_per_pixel,
+                packet_tracing: true,
+                integrator,
+            },
+        }
+    }
+
+    fn glowing(strength: Float) -> Box<dyn Material> {
+        Box::new(Emissive {
+            material: Box::new(Lambertian {
+                color: RGB::new(0, 0, 0),
+                color_texture: None,
+            }),
+            color: RGB::new(255, 200, 100),
+            strength,
+        })
+    }
+
+    #[test]
+    fn packets_match_single_rays() {
+        let runs = [
+            (1, Integrator::Whitted),
+            (3, Integrator::Whitted),
+            (2, Integrator::PathTracing),
+        ];
+
+        for &(samples, integrator) in &runs {
+            let scene = scene(samples, integrator);
+            let (width, height) = (scene.canvas.width, scene.canvas.height);
+
+            // 4x4 and 2x2 blocks, the canvas size leaves partial blocks at the edges.
+            for &size in &[4, 2] {
+                for y in (0..height).step_by(size) {
+                    for x in (0..width).step_by(size) {
+                        let (block_width, block_height) =
+                            (size.min(width - x), size.min(height - y));
+                        let block = render_block(x, y, block_width, block_height, &scene);
+
+                        for (i, color) in block.iter().enumerate() {
+                            let (px, py) = (x + i % block_width, y + i / block_width);
+                            let expected = render_pixel(px as Float, py as Float, &scene);
+                            assert_eq!(color.as_array(), expected.as_array());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Sends back the same light whichever way it arrives, without any renderer support.
+    struct Uniform;
+
+    impl Material for Uniform {
+        fn color(&self) -> RGB {
+            RGB::new(255, 255, 255)
+        }
+
+        fn evaluate(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Vec3 {
+            Vec3::new(0.25, 0.5, 1.)
+        }
+
+        fn sample(&self, _: &Surface, _: &Vec3, _: (Float, Float)) -> Option<Sample> {
+            None
+        }
+
+        fn pdf(&self, _surface: &Surface, _to_viewer: &Vec3, _to_light: &Vec3) -> Float {
+            0.
+        }
+    }
+
+    #[test]
+    fn user_defined_material() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(1, integrator);
+            scene.objects = vec![Box::new(Sphere::new(
+                Vec3::new(0., 0., -10.),
+                3.,
+                Box::new(Uniform),
+            ))];
+            scene.emitters = Emitters::new(&scene.objects);
+            scene.lights = vec![Light::new(Vec3::new(0., 0., 10.), 1. / PI)];
+
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let color = render_pixel(center_x as Float, center_y as Float, &scene);
+            assert_eq!(color.as_array(), [63, 127, 255]);
+            assert_eq!(render_pixel(0., 0., &scene).as_array(), [178, 178, 178]);
+        }
+    }
+
+    #[test]
+    fn glowing_objects_light_the_scene() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(16, integrator);
+            let white = Box::new(Lambertian {
+                color: RGB::new(255, 255, 255),
+                color_texture: None,
+            });
+            scene.objects = vec![
+                Box::new(Sphere::new(Vec3::new(0., 0., -10.), 1., glowing(1.))),
+                Box::new(Sphere::new(Vec3::new(-4., 0., -10.), 2.5, white)),
+            ];
+            scene.emitters = Emitters::new(&scene.objects);
+            scene.lights = Vec::new();
+            scene.options.background_color = RGB::new(0, 0, 0);
+
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let glow = render_pixel(center_x as Float, center_y as Float, &scene);
+            assert_eq!(glow.as_array(), [255, 200, 100]);
+
+            // The side of the white sphere facing the glow takes on its color, the other side
+            // stays dark.
+            let lit = render_pixel(center_x as Float - 4., center_y as Float, &scene).as_array();
+            assert!(lit[0] > 30 && lit[0] > lit[1] && lit[1] > lit[2], "{:?}", lit);
+            let dark = render_pixel(center_x as Float - 12., center_y as Float, &scene);
+            assert_eq!(dark.as_array(), [0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn light_shines_through_translucent_objects() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(8, integrator);
+            let wax = Translucent {
+                medium: Medium {
+                    scattering: Vec3::new(2., 2., 2.),
+                    absorption: Vec3::new(0.05, 0.2, 0.5),
+                },
+                ior: 1.4,
+                roughness: 0.3,
+            };
+            // Lit from behind only, the front is reached through the wax.
+            scene.objects = vec![Box::new(Sphere::new(
+                Vec3::new(0., 0., -10.),
+                1.,
+                Box::new(wax),
+            ))];
+            scene.emitters = Emitters::new(&scene.objects);
+            scene.lights = vec![Light::new(Vec3::new(0., 0., -20.), 1.)];
+            scene.options.background_color = RGB::new(0, 0, 0);
+
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let front = render_pixel(center_x as Float, center_y as Float, &scene).as_array();
+            assert!(front[0] > 10 && front[0] > front[1] && front[1] > front[2], "{:?}", front);
+        }
+    }
+
+    #[test]
+    fn rays_pass_through_cut_out_surfaces() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(1, integrator);
+            let screen = |opacity| {
+                Box::new(Cutout {
+                    material: Box::new(Lambertian {
+                        color: RGB::new(255, 255, 255),
+                        color_texture: None,
+                    }),
+                    opacity,
+                    opacity_texture: None,
+                    cutoff: 0.5,
+                })
+            };
+            // Between the camera, the light and a sphere, so both camera and shadow rays cross
+            // it.
+            scene.objects = vec![
+                Box::new(Sphere::new(Vec3::new(0., 0., -10.), 3., Box::new(Uniform))),
+                Box::new(Sphere::new(Vec3::new(0., 0., -5.), 1., screen(0.2))),
+            ];
+            scene.emitters = Emitters::new(&scene.objects);
+            scene.lights = vec![Light::new(Vec3::new(0., 0., 10.), 1. / PI)];
+
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let color = render_pixel(center_x as Float, center_y as Float, &scene);
+            assert_eq!(color.as_array(), [63, 127, 255]);
+            let block = render_block(center_x, center_y, 1, 1, &scene);
+            assert_eq!(block[0].as_array(), [63, 127, 255]);
+
+            scene.objects[1] = Box::new(Sphere::new(Vec3::new(0., 0., -5.), 1., screen(0.8)));
+            let color = render_pixel(center_x as Float, center_y as Float, &scene);
+            assert_ne!(color.as_array(), [63, 127, 255]);
+        }
+    }
+
+    #[test]
+    fn the_sun_casts_soft_shadows() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(16, integrator);
+            // Out of view, between the front of the sphere and the sun.
+            let blocker = Lambertian {
+                color: RGB::new(255, 255, 255),
+                color_texture: None,
+            };
+            scene.objects = vec![
+                Box::new(Sphere::new(Vec3::new(0., 0., -10.), 3., Box::new(Uniform))),
+                Box::new(Sphere::new(Vec3::new(6., 0., -1.), 1., Box::new(blocker))),
+            ];
+            scene.emitters = Emitters::new(&scene.objects);
+            scene.options.background_color = RGB::new(0, 0, 0);
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let mut center = |angular_diameter| {
+                let sun = Light::directional(Vec3::new(-1., 0., -1.), angular_diameter, 1. / PI);
+                scene.lights = vec![sun];
+                render_pixel(center_x as Float, center_y as Float, &scene).as_array()
+            };
+
+            assert_eq!(center(0.), [0, 0, 0]);
+            // Seen from the shadow, a wide sun peeks out around the blocker.
+            let penumbra = center(0.6);
+            assert!(penumbra[2] > 100 && penumbra[2] < 255, "{:?}", penumbra);
+        }
+    }
+
+    #[test]
+    fn lights_have_colors_and_fade_with_distance() {
+        for &integrator in &[Integrator::Whitted, Integrator::PathTracing] {
+            let mut scene = scene(1, integrator);
+            scene.objects = vec![Box::new(Sphere::new(
+                Vec3::new(0., 0., -10.),
+                3.,
+                Box::new(Uniform),
+            ))];
+            scene.emitters = Emitters::new(&scene.objects);
+            let (center_x, center_y) = (scene.canvas.width / 2, scene.canvas.height / 2);
+            let mut center = |attenuation| {
+                // 17 units from the front of the sphere.
+                let light = Light::new(Vec3::new(0., 0., 10.), 289. / PI)
+                    .with_color(RGB::new(255, 255, 0))
+                    .with_attenuation(attenuation);
+                scene.lights = vec![light];
+                render_pixel(center_x as Float, center_y as Float, &scene).as_array()
+            };
+
+            assert_eq!(center(Attenuation::InverseSquare), [63, 127, 0]);
+            let halved = center(Attenuation::Custom(Box::new(|distance| {
+                0.5 / (distance * distance)
+            })));
+            assert_eq!(halved, [31, 63, 0]);
+            assert_eq!(center(Attenuation::None), [255, 255, 0]);
+        }
     }
 }
@@ -1,75 +1,65 @@
+use crate::geometry::Float;
 use crate::geometry::vec3::{Vec3};
+use crate::math::mat4::Mat4;
+use crate::math::quaternion::Quaternion;
 
 pub struct Camera {
     pub position: Vec3,
-    pub rotation_matrix: [[f32; 3]; 3],
-    pub rotation: Vec3,
+    // Turns camera space, which looks down -z, into world space.
+    pub orientation: Quaternion,
+    // How far the camera travels while the shutter is open.
+    pub displacement: Vec3,
+    // How much the camera turns while the shutter is open.
+    pub spin: Quaternion,
 }
 
 impl Camera {
     pub fn new(position: Vec3) -> Self {
         Self {
             position,
-            rotation_matrix: [
-                [0.7071, 0., -0.7071],
-                [0., 1., 0.],
-                [0.7071, 0., 0.7071],
-            ],
-            rotation: Vec3::new(0., 0., 0.),
+            orientation: Quaternion::identity(),
+            displacement: Vec3::new(0., 0., 0.),
+            spin: Quaternion::identity(),
         }
     }
 
-    pub fn move_by(&mut self, distance: &Vec3) {
-        self.position = self.position.plus(distance);
+    pub fn looking_at(position: Vec3, target: &Vec3, up: &Vec3) -> Self {
+        let view = Mat4::look_at(&position, target, up);
+        let mut camera = Self::new(position);
+        // The view matrix of a camera is a pure rotation, so its transpose is the inverse.
+        camera.orientation = Quaternion::from_mat3(&view.linear().transpose());
+
+        camera
     }
 
-    pub fn rotate_by(&mut self, rotation: &Vec3) {
-        self.rotation = self.rotation.plus(rotation);
-
-        let alpha = self.rotation.x * 3.14 / 180.;
-        let beta = self.rotation.y * 3.14 / 180.;
-        let gamma = self.rotation.z * 3.14 / 180.;
-
-        let x_matrix = [
-            [1., 0., 0.,],
-            [0., gamma.cos(), -gamma.sin()],
-            [0., gamma.sin(), gamma.cos()]
-        ];
-        let y_matrix = [
-            [ beta.cos(), 0., beta.sin(),],
-            [0., 1., 0.],
-            [0., beta.sin(), beta.cos()]
-        ];
-        let z_matrix = [
-            [alpha.cos(), -alpha.sin(), 0.,],
-            [alpha.sin(), alpha.cos(), 0.],
-            [0., 0., 1.]
-        ];
-
-        let result = multiply_matrixes(z_matrix, multiply_matrixes(y_matrix, x_matrix));
-
-        self.rotation_matrix = result;
+    pub fn position_at(&self, time: Float) -> Vec3 {
+        self.position + self.displacement * time
     }
-}
 
-fn multiply_matrixes(mat1: [[f32; 3]; 3], mat2: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
-    let mut result: [[f32; 3]; 3] = [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]];
+    pub fn orientation_at(&self, time: Float) -> Quaternion {
+        Quaternion::identity().slerp(&self.spin, time) * self.orientation
+    }
 
-    for i in 0..3 {
-        for j in 0..3 {
-            for k in 0..3 {
-                result[i][j] += mat1[i][k] * mat2[k][j];
-            }
-        }
+    pub fn move_by(&mut self, distance: &Vec3) {
+        self.position += *distance;
     }
 
-    result
+    // Turns around the camera's own x, y and z axes by the given angles in degrees.
+    pub fn rotate_by(&mut self, rotation: &Vec3) {
+        let yaw = Quaternion::from_axis_angle(&Vec3::new(0., 1., 0.), rotation.y.to_radians());
+        let pitch = Quaternion::from_axis_angle(&Vec3::new(1., 0., 0.), rotation.x.to_radians());
+        let roll = Quaternion::from_axis_angle(&Vec3::new(0., 0., 1.), rotation.z.to_radians());
+
+        self.orientation = (self.orientation * yaw * pitch * roll).normalize();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::scene::camera::Camera;
-    use crate::geometry::vec3::Vec3;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::math::quaternion::Quaternion;
 
     #[test]
     fn move_by() {
@@ -84,30 +74,68 @@ mod tests {
     }
 
     #[test]
-    fn multiply_matrixes() {
-        let mat1 = [
-            [1., 2., 3.],
-            [4., 5., 6.],
-            [3., 2., 2.],
-        ];
-
-        let mat2 = [
-            [2., 3., 3.],
-            [1., 3., 2.],
-            [1., 1., 3.],
-        ];
-
-        let result = crate::scene::camera::multiply_matrixes(mat1, mat2);
-        let actual_result = [
-            [7., 12., 16.],
-            [19., 33., 40.],
-            [10., 17., 19.],
-        ];
-
-        for i in 0..3 {
-            for j in 0..3 {
-                assert_eq!(result[i][j], actual_result[i][j]);
-            }
-        }
+    fn position_at() {
+        let mut camera = Camera::new(Vec3::new(1., 0., 0.));
+        camera.displacement = Vec3::new(2., 0., -4.);
+
+        let position = camera.position_at(0.5);
+
+        assert_eq!(position.x, 2.);
+        assert_eq!(position.z, -2.);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rotate_by() {
+        let mut camera = Camera::new(Vec3::new(0., 0., 0.));
+        let forward = Vec3::new(0., 0., -1.);
+
+        camera.rotate_by(&Vec3::new(0., 90., 0.));
+        assert_approx_eq!(camera.orientation.rotate(&forward), Vec3::new(-1., 0., 0.));
+
+        // Pitching after the turn happens around the camera's own, already turned, x axis.
+        camera.rotate_by(&Vec3::new(90., 0., 0.));
+        assert_approx_eq!(camera.orientation.rotate(&forward), Vec3::new(0., 1., 0.));
+        assert_approx_eq!(
+            camera.orientation.rotate(&Vec3::new(0., 1., 0.)),
+            Vec3::new(1., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn looking_at() {
+        let camera = Camera::looking_at(
+            Vec3::new(1., 2., 3.),
+            &Vec3::new(4., 2., 3.),
+            &Vec3::new(0., 1., 0.),
+        );
+        assert_approx_eq!(
+            camera.orientation.rotate(&Vec3::new(0., 0., -1.)),
+            Vec3::new(1., 0., 0.)
+        );
+        assert_approx_eq!(
+            camera.orientation.rotate(&Vec3::new(0., 1., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+
+        let straight = Camera::looking_at(
+            Vec3::zero(),
+            &Vec3::new(0., 0., -1.),
+            &Vec3::new(0., 1., 0.),
+        );
+        assert_eq!(straight.orientation, Quaternion::identity());
+    }
+
+    #[test]
+    fn orientation_at() {
+        let mut camera = Camera::new(Vec3::new(0., 0., 0.));
+        camera.rotate_by(&Vec3::new(0., 30., 0.));
+        camera.spin = Quaternion::from_axis_angle(&Vec3::new(0., 1., 0.), 0.5);
+
+        assert_eq!(camera.orientation_at(0.), camera.orientation);
+        assert_approx_eq!(camera.orientation_at(1.), camera.spin * camera.orientation);
+
+        let halfway = camera.orientation_at(0.5).rotate(&Vec3::new(0., 0., -1.));
+        let angle = Float::to_radians(30.) + 0.25;
+        assert_approx_eq!(halfway, Vec3::new(-angle.sin(), 0., -angle.cos()));
+    }
+}
@@ -1,16 +1,589 @@
+use crate::geometry::Float;
+use crate::geometry::consts::PI;
+use crate::geometry::object::Object;
 use crate::geometry::vec3::{Vec3};
+use crate::utils::rgb::RGB;
 
 pub struct Light {
-    pub position: Vec3,
-    pub intensity: f32,
+    pub source: Source,
+    pub intensity: Float,
+    pub color: RGB,
+    pub attenuation: Attenuation,
+}
+
+pub enum Source {
+    Point(Vec3),
+    // Light from so far away that it arrives from the same direction everywhere, e.g. the sun.
+    // `direction` is the way the light travels. Seen as a disk `angular_diameter` radians wide,
+    // it casts shadows that are the softer the further they fall from what casts them.
+    Directional { direction: Vec3, angular_diameter: Float },
+    // A point shining along `direction`, at full intensity up to `inner_angle` off it and
+    // fading smoothly to nothing at `outer_angle`. A profile shapes the light further.
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        inner_angle: Float,
+        outer_angle: Float,
+        profile: Option<Profile>,
+    },
+}
+
+// How light from points and spots dims with the distance. Directional lights never do.
+pub enum Attenuation {
+    // As bright at any distance, so lights can be placed without worrying about their reach.
+    None,
+    // As real lights do, a light of intensity 1 is as bright one unit away as it is unattenuated.
+    InverseSquare,
+    // Share of the intensity left at a distance, for lights that reach only so far.
+    Custom(Box<dyn Fn(Float) -> Float + Send + Sync>),
+}
+
+// Light given off by a real fixture in every direction, measured by its maker and shipped in
+// IES files. Vertical angles are in degrees from the fixture's axis, horizontal ones around it,
+// values are relative to the brightest direction so the light's intensity still applies.
+pub struct Profile {
+    vertical: Vec<Float>,
+    horizontal: Vec<Float>,
+    // By horizontal angle, then by vertical angle.
+    values: Vec<Vec<Float>>,
+}
+
+impl Profile {
+    // Angles go up, there's a row of values for every horizontal angle and a value in each row
+    // for every vertical one. Neither may be empty.
+    pub fn new(vertical: Vec<Float>, horizontal: Vec<Float>, values: Vec<Vec<Float>>) -> Self {
+        assert!(
+            !vertical.is_empty() && !horizontal.is_empty(),
+            "Profile must have at least one angle each way"
+        );
+        assert_eq!(
+            values.len(),
+            horizontal.len(),
+            "Profile needs a row of values for every horizontal angle"
+        );
+        assert!(
+            values.iter().all(|row| row.len() == vertical.len()),
+            "Profile needs a value in each row for every vertical angle"
+        );
+
+        let brightest = values.iter().flatten().fold(0., |max: Float, &value| max.max(value));
+        let values = values
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|value| value / brightest.max(Float::EPSILON))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            vertical,
+            horizontal,
+            values,
+        }
+    }
+
+    // Interpolated between the measured angles. Horizontal angles are folded into the range
+    // the profile covers, which tells its symmetry: none beyond 0 for round fixtures, 90 for
+    // ones with four alike quarters and 180 for ones with two alike halves.
+    pub fn value(&self, vertical: Float, horizontal: Float) -> Float {
+        let horizontal = horizontal.rem_euclid(360.);
+        let horizontal = match self.horizontal.last() {
+            Some(&last) if last <= 90. => {
+                let half = horizontal % 180.;
+                half.min(180. - half)
+            }
+            Some(&last) if last <= 180. => horizontal.min(360. - horizontal),
+            _ => horizontal,
+        };
+
+        // Nothing is given off past the measured vertical angles.
+        let (first, last) = (self.vertical[0], self.vertical[self.vertical.len() - 1]);
+        if vertical < first || vertical > last {
+            return 0.;
+        }
+
+        let (column, fraction) = interpolation(&self.vertical, vertical);
+        let along = |row: &Vec<Float>| match row.get(column + 1) {
+            Some(next) => row[column] * (1. - fraction) + next * fraction,
+            None => row[column],
+        };
+        let (row, blend) = interpolation(&self.horizontal, horizontal);
+
+        match self.values.get(row + 1) {
+            Some(next) => along(&self.values[row]) * (1. - blend) + along(next) * blend,
+            None => along(&self.values[row]),
+        }
+    }
+}
+
+// Index of the angle below `angle` and how far it is towards the next one. Angles past either
+// end stick to it.
+fn interpolation(angles: &[Float], angle: Float) -> (usize, Float) {
+    let index = angles
+        .partition_point(|&a| a <= angle)
+        .saturating_sub(1)
+        .min(angles.len().saturating_sub(2));
+
+    match angles.get(index + 1) {
+        Some(&next) if next > angles[index] => {
+            let fraction = (angle - angles[index]) / (next - angles[index]);
+            (index, fraction.clamp(0., 1.))
+        }
+        _ => (index, 0.),
+    }
 }
 
 impl Light {
-    pub fn new(position: Vec3, intensity: f32) -> Self {
-        Self { position, intensity }
+    // White and unattenuated, see `with_color` and `with_attenuation`.
+    pub fn new(position: Vec3, intensity: Float) -> Self {
+        Self::from_source(Source::Point(position), intensity)
+    }
+
+    pub fn directional(direction: Vec3, angular_diameter: Float, intensity: Float) -> Self {
+        let source = Source::Directional {
+            direction: direction.normalize(),
+            angular_diameter,
+        };
+
+        Self::from_source(source, intensity)
+    }
+
+    // Angles are in radians.
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        inner_angle: Float,
+        outer_angle: Float,
+        intensity: Float,
+    ) -> Self {
+        let source = Source::Spot {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            profile: None,
+        };
+
+        Self::from_source(source, intensity)
+    }
+
+    fn from_source(source: Source, intensity: Float) -> Self {
+        Self {
+            source,
+            intensity,
+            color: RGB::new(255, 255, 255),
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn with_color(mut self, color: RGB) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    // Only spot lights take a profile.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        if let Source::Spot { profile: spot, .. } = &mut self.source {
+            *spot = Some(profile);
+        }
+        self
     }
 
-    pub fn set_intensity(&mut self, intensity: f32) {
+    pub fn set_intensity(&mut self, intensity: Float) {
         self.intensity = intensity;
     }
-}
\ No newline at end of file
+
+    // Direction towards the light from `point` and how far away it is, picked within the disk
+    // of directional lights with two uniform numbers in 0..1.
+    pub fn direction_from(&self, point: &Vec3, random: (Float, Float)) -> (Vec3, Float) {
+        match &self.source {
+            Source::Point(position) | Source::Spot { position, .. } => {
+                let offset = *position - *point;
+                let distance = offset.length();
+                (offset / distance, distance)
+            }
+            Source::Directional {
+                direction,
+                angular_diameter,
+            } => {
+                let cos_max = (angular_diameter / 2.).cos();
+                let cos = 1. - random.0 * (1. - cos_max);
+                let sin = (1. - cos * cos).max(0.).sqrt();
+                let angle = 2. * PI * random.1;
+                let (tangent, bitangent) = (-*direction).orthonormal_basis();
+
+                let towards = tangent * (sin * angle.cos()) + bitangent * (sin * angle.sin())
+                    - *direction * cos;
+                (towards, Float::INFINITY)
+            }
+        }
+    }
+
+    // Light arriving from `distance` away along `to_light`, per color channel.
+    pub fn radiance(&self, to_light: &Vec3, distance: Float) -> Vec3 {
+        let left = match (&self.source, &self.attenuation) {
+            (Source::Directional { .. }, _) | (_, Attenuation::None) => 1.,
+            (_, Attenuation::InverseSquare) => 1. / (distance * distance),
+            (_, Attenuation::Custom(curve)) => curve(distance),
+        };
+
+        self.color.as_vector() / 255. * (self.intensity * self.falloff(to_light) * left)
+    }
+
+    // Share of the intensity sent the opposite way of `to_light`.
+    pub fn falloff(&self, to_light: &Vec3) -> Float {
+        let (direction, inner_angle, outer_angle, profile) = match &self.source {
+            Source::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+                profile,
+                ..
+            } => (direction, *inner_angle, *outer_angle, profile),
+            _ => return 1.,
+        };
+        let cos = -direction.dot_product(to_light);
+        let (cos_inner, cos_outer) = (inner_angle.cos(), outer_angle.cos());
+
+        let cone = if cos >= cos_inner {
+            1.
+        } else if cos <= cos_outer {
+            0.
+        } else {
+            let t = (cos - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3. - 2. * t)
+        };
+
+        match profile {
+            Some(profile) if cone > 0. => {
+                let (tangent, bitangent) = direction.orthonormal_basis();
+                let horizontal = (-bitangent.dot_product(to_light))
+                    .atan2(-tangent.dot_product(to_light))
+                    .to_degrees();
+                cone * profile.value(cos.clamp(-1., 1.).acos().to_degrees(), horizontal)
+            }
+            _ => cone,
+        }
+    }
+}
+
+pub enum Shape {
+    Sphere { center: Vec3, radius: Float },
+    Triangle([Vec3; 3]),
+}
+
+// Glowing part of an object. Lights the scene from every point of its surface, so it casts
+// soft shadows.
+pub struct Emitter {
+    pub shape: Shape,
+    // See `Material::emission`.
+    pub radiance: Vec3,
+    // How far the shape moves while the shutter is open, as in `Moving`.
+    pub displacement: Vec3,
+}
+
+// A direction towards a point of an emitter.
+pub struct EmitterSample {
+    pub direction: Vec3,
+    pub distance: Float,
+    // Density of picking the direction, per solid angle.
+    pub pdf: Float,
+}
+
+impl Emitter {
+    pub fn new(shape: Shape, radiance: Vec3) -> Self {
+        Self {
+            shape,
+            radiance,
+            displacement: Vec3::zero(),
+        }
+    }
+
+    pub fn area(&self) -> Float {
+        match &self.shape {
+            Shape::Sphere { radius, .. } => 4. * PI * radius * radius,
+            Shape::Triangle([a, b, c]) => (*b - *a).cross(&(*c - *a)).length() / 2.,
+        }
+    }
+
+    // Light given off in total, up to a constant factor.
+    pub fn power(&self) -> Float {
+        self.area() * (self.radiance.x + self.radiance.y + self.radiance.z) / 3.
+    }
+
+    // Picks a point of the emitter as seen from `point` at `time`, with two uniform numbers in
+    // 0..1. Spheres seen from outside are sampled within the cone they cover, which wastes no
+    // samples on their far side.
+    pub fn sample(
+        &self,
+        point: &Vec3,
+        time: Float,
+        random: (Float, Float),
+    ) -> Option<EmitterSample> {
+        let point = *point - self.displacement * time;
+
+        match &self.shape {
+            Shape::Sphere { center, radius } => sample_sphere(&point, center, *radius, random),
+            Shape::Triangle(vertices) => sample_triangle(&point, vertices, random),
+        }
+    }
+}
+
+// All emitters of a scene, picked in proportion to the light they give off.
+pub struct Emitters {
+    emitters: Vec<Emitter>,
+    // Running totals of their power.
+    cumulative: Vec<Float>,
+}
+
+impl Emitters {
+    pub fn new(objects: &[Box<dyn Object>]) -> Self {
+        let emitters: Vec<Emitter> = objects
+            .iter()
+            .flat_map(|object| object.emitters())
+            .filter(|emitter| emitter.power() > 0.)
+            .collect();
+        let mut total = 0.;
+        let cumulative = emitters
+            .iter()
+            .map(|emitter| {
+                total += emitter.power();
+                total
+            })
+            .collect();
+
+        Self {
+            emitters,
+            cumulative,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty()
+    }
+
+    // The emitter at `u` in 0..1 and the probability of picking it.
+    pub fn pick(&self, u: Float) -> Option<(&Emitter, Float)> {
+        let total = *self.cumulative.last()?;
+        let index = self
+            .cumulative
+            .partition_point(|&sum| sum <= u * total)
+            .min(self.emitters.len() - 1);
+        let start = if index == 0 {
+            0.
+        } else {
+            self.cumulative[index - 1]
+        };
+        let probability = (self.cumulative[index] - start) / total;
+
+        Some((&self.emitters[index], probability))
+    }
+}
+
+fn sample_sphere(
+    point: &Vec3,
+    center: &Vec3,
+    radius: Float,
+    random: (Float, Float),
+) -> Option<EmitterSample> {
+    let axis = *center - *point;
+    let distance_sqr = axis.dot_product(&axis);
+    let sin_sqr_max = radius * radius / distance_sqr;
+    let angle = 2. * PI * random.1;
+
+    // From inside, or on the sphere itself, any of its points may be in sight.
+    if sin_sqr_max >= 1. {
+        let cos = 1. - 2. * random.0;
+        let sin = (1. - cos * cos).max(0.).sqrt();
+        let normal = Vec3::new(sin * angle.cos(), sin * angle.sin(), cos);
+
+        let area = 4. * PI * radius * radius;
+
+        return towards_surface(point, &(*center + normal * radius), &normal, area);
+    }
+
+    // 1 - cos of the cone's half angle, written to keep its precision for distant spheres.
+    let cone = sin_sqr_max / (1. + (1. - sin_sqr_max).sqrt());
+    let cos = 1. - random.0 * cone;
+    let sin = (1. - cos * cos).max(0.).sqrt();
+
+    let length = distance_sqr.sqrt();
+    let (tangent, bitangent) = (axis / length).orthonormal_basis();
+    let direction =
+        tangent * (sin * angle.cos()) + bitangent * (sin * angle.sin()) + axis / length * cos;
+    // The nearer of the two points of the sphere along the direction.
+    let distance = length * cos - (radius * radius - distance_sqr * sin * sin).max(0.).sqrt();
+
+    Some(EmitterSample {
+        direction,
+        distance,
+        pdf: 1. / (2. * PI * cone),
+    })
+}
+
+fn sample_triangle(
+    point: &Vec3,
+    vertices: &[Vec3; 3],
+    random: (Float, Float),
+) -> Option<EmitterSample> {
+    let [a, b, c] = *vertices;
+    // Barycentric coordinates of `a` and `b`, uniform over the triangle.
+    let root = random.0.sqrt();
+    let (u, v) = (1. - root, random.1 * root);
+    let normal = (b - a).cross(&(c - a));
+
+    towards_surface(
+        point,
+        &(a * u + b * v + c * (1. - u - v)),
+        &normal.normalize(),
+        normal.length() / 2.,
+    )
+}
+
+// Converts the density of picking `target` uniformly over an area to one per solid angle.
+fn towards_surface(
+    point: &Vec3,
+    target: &Vec3,
+    normal: &Vec3,
+    area: Float,
+) -> Option<EmitterSample> {
+    let offset = *target - *point;
+    let distance = offset.length();
+    let direction = offset / distance;
+    let cos = normal.dot_product(&direction).abs();
+
+    if distance <= 0. || cos <= 0. {
+        return None;
+    }
+
+    Some(EmitterSample {
+        direction,
+        distance,
+        pdf: distance * distance / (area * cos),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::material::emissive::Emissive;
+    use crate::geometry::material::lambertian::Lambertian;
+    use crate::geometry::material::Material;
+    use crate::geometry::object::Object;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::sphere_set::SphereSet;
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::loaders::materials::MaterialLibrary;
+    use crate::scene::light::{Emitter, Emitters, Light, Profile, Shape};
+    use crate::utils::random::Random;
+    use crate::utils::rgb::RGB;
+
+    fn glowing(strength: Float) -> Box<dyn Material> {
+        Box::new(Emissive {
+            material: Box::new(Lambertian {
+                color: RGB::new(0, 0, 0),
+                color_texture: None,
+            }),
+            color: RGB::new(255, 255, 255),
+            strength,
+        })
+    }
+
+    #[test]
+    fn emitters_are_picked_by_power() {
+        let mut spheres = SphereSet::new();
+        spheres.push(Sphere::new(Vec3::new(0., 0., -10.), 1., glowing(1.)));
+        let glass = MaterialLibrary::builtin().get("glass").unwrap();
+        spheres.push(Sphere::new(Vec3::new(5., 0., -10.), 1., glass));
+        spheres.push(Sphere::new(Vec3::new(-5., 0., -10.), 1., glowing(3.)));
+        let objects: Vec<Box<dyn Object>> = vec![Box::new(spheres)];
+        let emitters = Emitters::new(&objects);
+
+        let (dim, probability) = emitters.pick(0.2).unwrap();
+        assert_approx_eq!(dim.radiance, Vec3::new(1., 1., 1.));
+        assert_approx_eq!(probability, 0.25);
+        let (bright, probability) = emitters.pick(0.3).unwrap();
+        assert_approx_eq!(bright.radiance, Vec3::new(3., 3., 3.));
+        assert_approx_eq!(probability, 0.75);
+
+        assert!(Emitters::new(&[]).pick(0.5).is_none());
+    }
+
+    #[test]
+    fn samples_land_on_emitters() {
+        let mut random = Random::new(3);
+        let center = Vec3::new(1., 2., -6.);
+        let sphere = Emitter::new(Shape::Sphere { center, radius: 2. }, Vec3::new(1., 1., 1.));
+
+        for point in &[Vec3::zero(), Vec3::new(1., 2.5, -6.)] {
+            for _ in 0..100 {
+                let sample = sphere.sample(point, 0., random.pair()).unwrap();
+                let target = *point + sample.direction * sample.distance;
+
+                assert_approx_eq!((target - center).length(), 2., 1e-3);
+                assert!(sample.pdf > 0.);
+            }
+        }
+
+        // Directions picked on a triangle cover the solid angle it subtends (Van Oosterom and
+        // Strackee), on average.
+        let (a, b, c) = (
+            Vec3::new(0., 0., -1.),
+            Vec3::new(2., 0., -1.),
+            Vec3::new(0., 1., -2.),
+        );
+        let triangle = Emitter::new(Shape::Triangle([a, b, c]), Vec3::new(1., 1., 1.));
+        let (la, lb, lc) = (a.length(), b.length(), c.length());
+        let solid_angle = 2.
+            * (a.dot_product(&b.cross(&c)).abs()
+                / (la * lb * lc
+                    + a.dot_product(&b) * lc
+                    + a.dot_product(&c) * lb
+                    + b.dot_product(&c) * la))
+                .atan();
+
+        let count = 20_000;
+        let covered = (0..count).fold(0., |sum, _| {
+            let sample = triangle.sample(&Vec3::zero(), 0., random.pair()).unwrap();
+            sum + 1. / sample.pdf
+        }) / count as Float;
+        assert_approx_eq!(covered, solid_angle, solid_angle * 0.02);
+
+        // Moving emitters are sampled where they are at the time.
+        let moved = Emitter {
+            displacement: Vec3::new(0., 0., 10.),
+            ..triangle
+        };
+        let sample = moved.sample(&Vec3::new(0., 0., 5.), 0.5, (0.5, 0.5));
+        assert!(sample.unwrap().direction.z < 0.);
+    }
+
+    #[test]
+    fn spot_lights_fade_towards_the_edge() {
+        let spot = Light::spot(Vec3::zero(), Vec3::new(0., -1., 0.), 0.2, 0.6, 1.);
+        // Towards the light from points below it.
+        let from = |x: Float| Vec3::new(-x, 1., 0.).normalize();
+
+        assert_eq!(spot.falloff(&from(0.)), 1.);
+        assert_eq!(spot.falloff(&from(0.2)), 1.);
+        let between = spot.falloff(&from(0.4_f64.tan() as Float));
+        assert!(between > 0.3 && between < 0.8, "{}", between);
+        assert_eq!(spot.falloff(&from(1.)), 0.);
+        assert_eq!(spot.falloff(&Vec3::new(0., -1., 0.)), 0.);
+
+        // Half as bright off to the side, whichever way.
+        let profile = Profile::new(vec![0., 90.], vec![0.], vec![vec![4., 2.]]);
+        let spot = Light::spot(Vec3::zero(), Vec3::new(0., 0., -1.), 3., 3., 1.)
+            .with_profile(profile);
+        assert_approx_eq!(spot.falloff(&Vec3::new(0., 0., 1.)), 1.);
+        assert_approx_eq!(spot.falloff(&Vec3::new(1., 0., 0.)), 0.5);
+        assert_approx_eq!(spot.falloff(&Vec3::new(0., -1., 0.)), 0.5);
+        assert_eq!(spot.falloff(&Vec3::new(0., 0., -1.)), 0.);
+    }
+}
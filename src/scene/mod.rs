@@ -1,14 +1,17 @@
 use self::camera::Camera;
-use self::light::Light;
-use crate::geometry::sphere::Sphere;
+use self::light::{Emitters, Light};
+use crate::geometry::Float;
+use crate::geometry::object::Object;
 use crate::utils::rgb::RGB;
 
 pub mod camera;
 pub mod light;
 
 pub struct Scene {
-    pub objects: Vec<Sphere>,
+    pub objects: Vec<Box<dyn Object>>,
     pub lights: Vec<Light>,
+    // Glowing parts of `objects`, see `Emitters::new`.
+    pub emitters: Emitters,
     pub camera: Camera,
     pub canvas: Canvas,
     pub options: SceneOptions,
@@ -17,10 +20,25 @@ pub struct Scene {
 pub struct SceneOptions {
     pub reflections_limit: usize,
     pub background_color: RGB,
+    // Samples spread over the shutter interval, more than one renders motion blur.
+    pub samples_per_pixel: usize,
+    // Traces primary rays of neighbouring pixels together, same image as one ray at a time.
+    pub packet_tracing: bool,
+    pub integrator: Integrator,
+}
+
+// How the light reaching the camera is gathered, both work with every material.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Integrator {
+    // Point lights plus recursive mirror reflection and refraction. Noise free.
+    Whitted,
+    // Continues each path in a direction sampled from the material, which also lights surfaces
+    // with the background and rough reflections. Needs many samples per pixel to converge.
+    PathTracing,
 }
 
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
-    pub fov: f32,
+    pub fov: Float,
 }
@@ -172,25 +172,25 @@ fn handle_event(event: Event, scene: &mut Scene) {
             keycode: Some(Keycode::Kp8),
             ..
         } => {
-            scene.camera.rotate_by(&Vec3::new(0., 0., 1.));
+            scene.camera.rotate_by(&Vec3::new(1., 0., 0.));
         }
         Event::KeyDown {
             keycode: Some(Keycode::Kp2),
             ..
         } => {
-            scene.camera.rotate_by(&Vec3::new(0., 0., -1.));
+            scene.camera.rotate_by(&Vec3::new(-1., 0., 0.));
         }
         Event::KeyDown {
             keycode: Some(Keycode::Kp7),
             ..
         } => {
-            scene.camera.rotate_by(&Vec3::new(1., 0., 0.));
+            scene.camera.rotate_by(&Vec3::new(0., 0., 1.));
         }
         Event::KeyDown {
             keycode: Some(Keycode::Kp9),
             ..
         } => {
-            scene.camera.rotate_by(&Vec3::new(-1., 0., 0.));
+            scene.camera.rotate_by(&Vec3::new(0., 0., -1.));
         }
         _ => {}
     }
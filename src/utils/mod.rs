@@ -1,12 +1,104 @@
+use crate::geometry::ray::Ray;
 use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
 
-pub mod material_factory;
+pub mod noise;
+pub mod random;
 pub mod rgb;
+pub mod spectrum;
+
+// Relative to the largest coordinate of the hit point, or of the ray's origin plus the distance
+// it travelled when that's larger, which bounds the rounding error of the hit. The offset suits
+// scenes measured in millimeters as well as in kilometers, and small objects seen from afar.
+const SURFACE_OFFSET: Float = Float::EPSILON * 256.;
+
+// `ray` is the one that hit `point`, shortened to the hit.
+pub fn move_from_surface(ray: &Ray, direction: &Vec3, normal: &Vec3, point: &Vec3) -> Vec3 {
+    let largest = |vector: &Vec3| vector.x.abs().max(vector.y.abs()).max(vector.z.abs());
+    let magnitude = largest(point).max(largest(&ray.origin) + ray.t);
+    let offset = (magnitude * SURFACE_OFFSET).max(Float::MIN_POSITIVE);
 
-pub fn move_from_surface(direction: &Vec3, normal: &Vec3, point: &Vec3) -> Vec3 {
     if direction.dot_product(normal) < 0. {
-        point.minus(&normal.scale(1e-3))
+        *point - *normal * offset
     } else {
-        point.plus(&normal.scale(1e-3))
+        *point + *normal * offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::vec3::Vec3;
+    use crate::geometry::Float;
+    use crate::loaders::materials::MaterialLibrary;
+    use crate::utils::move_from_surface;
+
+    // Rays leaving a sphere of radius `scale` around `center` must neither hit the surface they
+    // start on (acne) nor start further off it than `leak` (light leaks).
+    fn check_offsets(scale: Float, camera: Vec3, center: Vec3, leak: Float) {
+        let glass = MaterialLibrary::builtin().get("glass").unwrap();
+        let sphere = Sphere::new(center, scale, glass);
+        let light = center + Vec3::new(10., 10., 10.) * scale;
+
+        for i in 0..15 {
+            for j in 0..15 {
+                let offset = Vec3::new(i as Float - 7., j as Float - 7., 0.) * (scale / 10.);
+                let mut ray = Ray::new(camera, (center + offset - camera).normalize(), Float::MAX);
+                assert!(sphere.ray_intersect(&mut ray));
+
+                let point = ray.origin + ray.direction * ray.t;
+                let normal = (point - center).normalize();
+
+                let light_direction = (light - point).normalize();
+                if light_direction.dot_product(&normal) > 0. {
+                    let origin = move_from_surface(&ray, &light_direction, &normal, &point);
+                    let mut shadow_ray = Ray::new(origin, light_direction, Float::MAX);
+                    assert!(!sphere.ray_intersect(&mut shadow_ray), "acne at scale {}", scale);
+                }
+
+                let inside = -normal;
+                let origin = move_from_surface(&ray, &inside, &normal, &point);
+                let mut refracted_ray = Ray::new(origin, inside, Float::MAX);
+                assert!(sphere.ray_intersect(&mut refracted_ray));
+                assert!(refracted_ray.t > scale, "acne at scale {}", scale);
+                assert!((origin - point).length() < leak, "leak at scale {}", scale);
+            }
+        }
+    }
+
+    // A sphere of radius `scale` in front of the camera.
+    fn check_scene(scale: Float, camera: Vec3) {
+        let center = camera + Vec3::new(3., -2., -20.) * scale;
+        check_offsets(scale, camera, center, scale * 1e-3);
+    }
+
+    #[test]
+    fn tiny_scene() {
+        check_scene(1e-4, Vec3::zero());
+    }
+
+    #[test]
+    fn unit_scene() {
+        check_scene(1., Vec3::zero());
+    }
+
+    #[test]
+    fn huge_scene() {
+        check_scene(1e4, Vec3::zero());
+    }
+
+    // Single precision can't resolve a meter-sized object a thousand kilometers from the origin.
+    #[cfg(feature = "f64")]
+    #[test]
+    fn small_object_far_away() {
+        check_scene(1., Vec3::new(1e6, 0., -1e6));
+    }
+
+    // Hits are only as precise as the ray that found them, however close to the origin they are.
+    #[test]
+    fn small_object_at_the_origin_seen_from_afar() {
+        let camera = Vec3::new(0.3, 0.2, 2.);
+        check_offsets(1e-2, camera, Vec3::zero(), camera.length() * 1e-3);
     }
-}
\ No newline at end of file
+}
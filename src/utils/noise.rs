@@ -0,0 +1,158 @@
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+// Directions from the center of a cube to the middles of its edges, as in improved Perlin noise.
+const GRADIENTS: [(Float, Float, Float); 12] = [
+    (1., 1., 0.),
+    (-1., 1., 0.),
+    (1., -1., 0.),
+    (-1., -1., 0.),
+    (1., 0., 1.),
+    (-1., 0., 1.),
+    (1., 0., -1.),
+    (-1., 0., -1.),
+    (0., 1., 1.),
+    (0., -1., 1.),
+    (0., 1., -1.),
+    (0., -1., -1.),
+];
+
+// Gradient noise with one gradient per lattice point, roughly in -1..1 and zero at the points
+// themselves. Gradients come from hashing the lattice coordinates instead of a permutation
+// table, so the noise doesn't repeat.
+pub fn perlin(point: &Vec3) -> Float {
+    let cell = (point.x.floor(), point.y.floor(), point.z.floor());
+    let (x, y, z) = (point.x - cell.0, point.y - cell.1, point.z - cell.2);
+    let cell = (cell.0 as i64, cell.1 as i64, cell.2 as i64);
+
+    let corner = |dx: i64, dy: i64, dz: i64| {
+        let (gx, gy, gz) = GRADIENTS[hash(cell.0 + dx, cell.1 + dy, cell.2 + dz) as usize % 12];
+        gx * (x - dx as Float) + gy * (y - dy as Float) + gz * (z - dz as Float)
+    };
+    let lerp = |a: Float, b: Float, t: Float| a + (b - a) * t;
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    lerp(
+        lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        ),
+        lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        ),
+        w,
+    )
+}
+
+// Sum of `octaves` layers of absolute noise, each twice as fine and half as strong as the
+// previous one. Mostly in 0..1.
+pub fn turbulence(point: &Vec3, octaves: usize) -> Float {
+    let mut sum = 0.;
+    let mut scale = 1.;
+
+    for _ in 0..octaves {
+        sum += perlin(&(*point * scale)).abs() / scale;
+        scale *= 2.;
+    }
+
+    sum
+}
+
+// Distance to the closest of a set of random points, one in every lattice cell. Zero at the
+// points, rarely above 1.
+pub fn voronoi(point: &Vec3) -> Float {
+    let cell = (
+        point.x.floor() as i64,
+        point.y.floor() as i64,
+        point.z.floor() as i64,
+    );
+    let mut closest = Float::MAX;
+
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let distance =
+                    (feature_point(cell.0 + dx, cell.1 + dy, cell.2 + dz) - *point).length();
+                closest = closest.min(distance);
+            }
+        }
+    }
+
+    closest
+}
+
+// The random point of a Voronoi cell.
+fn feature_point(x: i64, y: i64, z: i64) -> Vec3 {
+    let first = hash(x, y, z);
+    let second = mix(first);
+    let third = mix(second);
+    let unit = |hash: u32| (hash >> 8) as Float / (1 << 24) as Float;
+
+    Vec3::new(
+        x as Float + unit(first),
+        y as Float + unit(second),
+        z as Float + unit(third),
+    )
+}
+
+// Smooth step with zero first and second derivatives at both ends, hides the lattice.
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn hash(x: i64, y: i64, z: i64) -> u32 {
+    mix((x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f))
+}
+
+fn mix(mut hash: u32) -> u32 {
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::geometry::Float;
+    use crate::utils::noise::{feature_point, perlin, turbulence, voronoi};
+
+    #[test]
+    fn perlin_is_smooth_and_bounded() {
+        assert_eq!(perlin(&Vec3::new(3., -2., 7.)), 0.);
+
+        let mut previous = perlin(&Vec3::new(0.3, 0.7, 0.1));
+        let mut varies = false;
+        for step in 1..1000 {
+            let value = perlin(&Vec3::new(0.3 + step as Float * 0.01, 0.7, 0.1));
+
+            assert!(value.abs() <= 1.1);
+            assert!((value - previous).abs() < 0.05);
+            varies |= (value - previous).abs() > 1e-3;
+            previous = value;
+        }
+        assert!(varies);
+    }
+
+    #[test]
+    fn turbulence_adds_octaves() {
+        let point = Vec3::new(0.4, 1.3, -2.6);
+
+        assert_approx_eq!(turbulence(&point, 1), perlin(&point).abs());
+        assert!(turbulence(&point, 4) >= turbulence(&point, 1));
+    }
+
+    #[test]
+    fn voronoi_is_zero_at_feature_points() {
+        let feature = feature_point(2, -1, 5);
+
+        assert_approx_eq!(voronoi(&feature), 0.);
+        assert!(voronoi(&(feature + Vec3::new(0.05, 0., 0.))) <= 0.05 + 1e-5);
+    }
+}
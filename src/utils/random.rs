@@ -0,0 +1,56 @@
+use crate::geometry::Float;
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+// PCG32 (O'Neill), small and plenty for sampling. Seeded per pixel sample so that renders are
+// reproducible and don't depend on how pixels are split between threads or packets.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        let mut random = Self {
+            state: seed.wrapping_add(INCREMENT),
+        };
+        random.next_u32();
+
+        random
+    }
+
+    // Uniform in 0..1, never 1.
+    pub fn uniform(&mut self) -> Float {
+        (self.next_u32() >> 8) as Float / (1 << 24) as Float
+    }
+
+    pub fn pair(&mut self) -> (Float, Float) {
+        (self.uniform(), self.uniform())
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Float;
+    use crate::utils::random::Random;
+
+    #[test]
+    fn uniform() {
+        let mut random = Random::new(42);
+        let values: Vec<Float> = (0..10_000).map(|_| random.uniform()).collect();
+        let mean = values.iter().sum::<Float>() / values.len() as Float;
+
+        assert!(values.iter().all(|&value| (0. ..1.).contains(&value)));
+        assert!((mean - 0.5).abs() < 0.01);
+        assert_ne!(Random::new(1).uniform(), Random::new(2).uniform());
+        assert_eq!(Random::new(5).pair(), Random::new(5).pair());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::geometry::Float;
 use crate::geometry::vec3::Vec3;
 
 pub struct RGB {
@@ -20,7 +21,7 @@ impl RGB {
     }
 
     pub fn as_vector(&self) -> Vec3 {
-        Vec3::new(self.r as f32, self.g as f32, self.b as f32)
+        Vec3::new(self.r as Float, self.g as Float, self.b as Float)
     }
 
     pub fn as_array(&self) -> [u8; 3] {
@@ -0,0 +1,55 @@
+use crate::geometry::vec3::Vec3;
+use crate::geometry::Float;
+
+// Bands the visible spectrum is split into where light is traced one wavelength at a time,
+// e.g. through dispersive glass.
+pub const BANDS: usize = 16;
+// Ends of the visible spectrum in nanometers.
+const SHORTEST: Float = 400.;
+const LONGEST: Float = 700.;
+
+// Wavelength at `offset` in 0..1 across the band, in nanometers.
+pub fn wavelength(band: usize, offset: Float) -> Float {
+    SHORTEST + (band as Float + offset) * (LONGEST - SHORTEST) / BANDS as Float
+}
+
+// Share of white light falling into the band, per color channel. All bands add up to white.
+pub fn color(band: usize) -> Vec3 {
+    let total = (0..BANDS).fold(Vec3::zero(), |sum, band| {
+        sum + response(wavelength(band, 0.5))
+    });
+
+    response(wavelength(band, 0.5)) / total
+}
+
+// Rough sensitivity of the red, green and blue channels. Red picks up a little violet too, so
+// that the short end of a rainbow looks purple rather than blue.
+fn response(wavelength: Float) -> Vec3 {
+    let lobe = |peak: Float, width: Float| (-((wavelength - peak) / width).powi(2) / 2.).exp();
+
+    Vec3::new(
+        lobe(605., 40.) + 0.2 * lobe(420., 20.),
+        lobe(545., 40.),
+        lobe(450., 30.),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::vec3::{assert_approx_eq, Vec3};
+    use crate::utils::spectrum::{color, wavelength, BANDS};
+
+    #[test]
+    fn bands_add_up_to_white() {
+        let white = (0..BANDS).fold(Vec3::zero(), |sum, band| sum + color(band));
+
+        assert_approx_eq!(white, Vec3::new(1., 1., 1.));
+        assert_eq!(wavelength(0, 0.), 400.);
+        assert_eq!(wavelength(BANDS - 1, 1.), 700.);
+
+        // Red at the long end, blue at the short end.
+        let (blue, red) = (color(2), color(BANDS - 3));
+        assert!(blue.z > blue.x && blue.z > blue.y);
+        assert!(red.x > red.y && red.x > red.z);
+    }
+}